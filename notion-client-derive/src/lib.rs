@@ -0,0 +1,291 @@
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr};
+
+const KINDS: &[(&str, &str)] = &[
+    ("checkbox", "Checkbox"),
+    ("date", "Date"),
+    ("email", "Email"),
+    ("multi_select", "MultiSelect"),
+    ("number", "Number"),
+    ("people", "People"),
+    ("phone_number", "PhoneNumber"),
+    ("relation", "Relation"),
+    ("rich_text", "RichText"),
+    ("select", "Select"),
+    ("status", "Status"),
+    ("title", "Title"),
+    ("url", "Url"),
+];
+
+/// Maps the fields of a struct onto page properties, generating `TryFrom<&Page>` and
+/// `notion_client::mapping::NotionPage`.
+///
+/// Fields are looked up by their own name unless they have a `#[notion(name = "...")]` and/or
+/// `#[notion(id = "...")]` attribute, and are written as the property type of their Rust type
+/// unless they have a `#[notion(kind = "...")]` attribute. `#[notion(skip)]` fields are filled
+/// in with `Default::default()` and never written, `#[notion(read_only)]` fields are read but
+/// never written.
+#[proc_macro_derive(NotionPage, attributes(notion))]
+pub fn derive_notion_page(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand_notion_page(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Maps the unit variants of an enum onto select, status or multi select options by name.
+///
+/// Variants use their own name unless they have a `#[notion(name = "...")]` attribute.
+#[proc_macro_derive(NotionSelect, attributes(notion))]
+pub fn derive_notion_select(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand_notion_select(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct Attributes {
+    name: Option<LitStr>,
+    id: Option<LitStr>,
+    kind: Option<LitStr>,
+    skip: bool,
+    read_only: bool,
+}
+
+fn parse_attributes(attributes: &[syn::Attribute]) -> syn::Result<Attributes> {
+    let mut parsed = Attributes::default();
+
+    for attribute in attributes {
+        if !attribute.path().is_ident("notion") {
+            continue;
+        }
+
+        attribute.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                parsed.name = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("id") {
+                parsed.id = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("kind") {
+                parsed.kind = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("skip") {
+                parsed.skip = true;
+            } else if meta.path.is_ident("read_only") {
+                parsed.read_only = true;
+            } else {
+                return Err(meta.error("expected `name`, `id`, `kind`, `skip` or `read_only`"));
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(parsed)
+}
+
+fn expand_notion_page(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "NotionPage can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "NotionPage can only be derived for structs with named fields",
+        ));
+    };
+
+    let mut reads = Vec::new();
+    let mut writes = Vec::new();
+
+    for field in &fields.named {
+        let ident = field.ident.as_ref().expect("named field to have an ident");
+        let ty = &field.ty;
+        let attributes = parse_attributes(&field.attrs)?;
+
+        if attributes.skip {
+            reads.push(quote! { #ident: ::core::default::Default::default() });
+            continue;
+        }
+
+        let name = match (&attributes.name, &attributes.id) {
+            (Some(name), _) => Some(name.clone()),
+            (None, Some(_)) => None,
+            (None, None) => Some(LitStr::new(&ident.to_string(), ident.span())),
+        };
+        let label = name
+            .as_ref()
+            .or(attributes.id.as_ref())
+            .expect("field to have a name or an id");
+        let lookup_name = option_tokens(name.as_ref());
+        let lookup_id = option_tokens(attributes.id.as_ref());
+        // Notion accepts both names and ids as keys, but ids survive renames
+        let key = attributes.id.as_ref().or(name.as_ref());
+
+        reads.push(quote! {
+            #ident: <#ty as ::notion_client::mapping::FromPropertyField>::from_field(
+                #label,
+                ::notion_client::mapping::find_property(page, #lookup_name, #lookup_id),
+            )?
+        });
+
+        if attributes.read_only {
+            continue;
+        }
+
+        let kind = match &attributes.kind {
+            Some(kind) => {
+                let variant = KINDS
+                    .iter()
+                    .find(|(key, _)| *key == kind.value())
+                    .map(|(_, variant)| format_ident!("{}", variant))
+                    .ok_or_else(|| syn::Error::new_spanned(kind, "unknown property kind"))?;
+
+                quote! { ::notion_client::mapping::PropertyKind::#variant }
+            }
+            None => quote! { <#ty as ::notion_client::mapping::ToProperty>::KIND },
+        };
+
+        writes.push(quote! {
+            {
+                let kind = #kind;
+                let mut property = ::notion_client::mapping::Map::new();
+                property.insert(
+                    kind.key().to_owned(),
+                    ::notion_client::mapping::ToProperty::to_property(&self.#ident, kind),
+                );
+                properties.insert(
+                    (#key).to_owned(),
+                    ::notion_client::mapping::Value::Object(property),
+                );
+            }
+        });
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::core::convert::TryFrom<&::notion_client::Page> for #ident #ty_generics #where_clause {
+            type Error = ::notion_client::Error;
+
+            fn try_from(page: &::notion_client::Page) -> ::notion_client::Result<Self> {
+                ::core::result::Result::Ok(Self {
+                    #(#reads,)*
+                })
+            }
+        }
+
+        impl #impl_generics ::notion_client::mapping::NotionPage for #ident #ty_generics #where_clause {
+            fn to_properties(
+                &self,
+            ) -> ::notion_client::mapping::Map<::std::string::String, ::notion_client::mapping::Value> {
+                let mut properties = ::notion_client::mapping::Map::new();
+                #(#writes)*
+                properties
+            }
+        }
+    })
+}
+
+fn expand_notion_select(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "NotionSelect can only be derived for enums",
+        ));
+    };
+
+    let mut idents = Vec::new();
+    let mut names = Vec::new();
+
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "NotionSelect can only be derived for enums with unit variants",
+            ));
+        }
+
+        let attributes = parse_attributes(&variant.attrs)?;
+        idents.push(&variant.ident);
+        names.push(
+            attributes
+                .name
+                .unwrap_or_else(|| LitStr::new(&variant.ident.to_string(), Span::call_site())),
+        );
+    }
+
+    let ident = &input.ident;
+
+    Ok(quote! {
+        impl ::notion_client::mapping::SelectValue for #ident {
+            fn from_option_name(name: &str) -> ::core::option::Option<Self> {
+                match name {
+                    #(#names => ::core::option::Option::Some(Self::#idents),)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+
+            fn option_name(&self) -> ::std::string::String {
+                match self {
+                    #(Self::#idents => (#names).to_owned(),)*
+                }
+            }
+        }
+
+        impl ::notion_client::mapping::FromProperty for #ident {
+            fn from_property(
+                property: &::notion_client::Property,
+            ) -> ::core::result::Result<
+                ::core::option::Option<Self>,
+                ::notion_client::mapping::PropertyValueError,
+            > {
+                match property {
+                    ::notion_client::Property::Select { select: option, .. }
+                    | ::notion_client::Property::Status { status: option, .. } => match option {
+                        ::core::option::Option::Some(option) => {
+                            <Self as ::notion_client::mapping::SelectValue>::from_option_name(&option.name)
+                                .map(::core::option::Option::Some)
+                                .ok_or_else(|| {
+                                    ::notion_client::mapping::PropertyValueError::UnknownOption(
+                                        ::std::clone::Clone::clone(&option.name),
+                                    )
+                                })
+                        }
+                        ::core::option::Option::None => ::core::result::Result::Ok(::core::option::Option::None),
+                    },
+                    _ => ::core::result::Result::Err(::notion_client::mapping::PropertyValueError::WrongType),
+                }
+            }
+        }
+
+        impl ::notion_client::mapping::ToProperty for #ident {
+            const KIND: ::notion_client::mapping::PropertyKind =
+                ::notion_client::mapping::PropertyKind::Select;
+
+            fn to_property(
+                &self,
+                kind: ::notion_client::mapping::PropertyKind,
+            ) -> ::notion_client::mapping::Value {
+                ::notion_client::mapping::ToProperty::to_property(
+                    &<Self as ::notion_client::mapping::SelectValue>::option_name(self),
+                    kind,
+                )
+            }
+        }
+    })
+}
+
+fn option_tokens(value: Option<&LitStr>) -> TokenStream2 {
+    match value {
+        Some(value) => quote! { ::core::option::Option::Some(#value) },
+        None => quote! { ::core::option::Option::None },
+    }
+}
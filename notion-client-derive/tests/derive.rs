@@ -0,0 +1,184 @@
+use chrono::NaiveDate;
+use notion_client::mapping::NotionPage;
+use notion_client::{NotionPage, NotionSelect, Page};
+use serde_json::json;
+
+#[derive(Debug, PartialEq, NotionSelect)]
+enum Stage {
+    Backlog,
+    #[notion(name = "In progress")]
+    InProgress,
+    Done,
+}
+
+#[derive(Debug, PartialEq, NotionPage)]
+struct Task {
+    #[notion(id = "title", kind = "title")]
+    name: String,
+    #[notion(name = "Estimate")]
+    estimate: Option<f64>,
+    #[notion(name = "Stage", kind = "status")]
+    stage: Stage,
+    #[notion(name = "Tags")]
+    tags: Vec<String>,
+    #[notion(name = "Due")]
+    due: Option<NaiveDate>,
+    #[notion(name = "Blocked by", kind = "relation")]
+    blocked_by: Vec<String>,
+    #[notion(skip)]
+    notes: String,
+}
+
+fn page() -> Page {
+    serde_json::from_value(json!({
+        "id": "59833787-2cf9-4fdf-8782-e53db20768a5",
+        "created_by": { "id": "ee5f0f84-409a-440f-983a-a5315961c6e4" },
+        "url": "https://www.notion.so/59833787-2cf9-4fdf-8782-e53db20768a5",
+        "parent": { "type": "workspace", "workspace": true },
+        "created_time": "2022-03-01T19:05:00.000Z",
+        "last_edited_time": "2022-07-06T20:25:00.000Z",
+        "cover": null,
+        "icon": null,
+        "archived": false,
+        "properties": {
+            "Task name": {
+                "id": "title",
+                "type": "title",
+                "title": [{
+                    "type": "text",
+                    "text": { "content": "Write docs", "link": null },
+                    "annotations": {
+                        "bold": false,
+                        "italic": false,
+                        "strikethrough": false,
+                        "underline": false,
+                        "code": false,
+                        "color": "default"
+                    },
+                    "plain_text": "Write docs",
+                    "href": null
+                }]
+            },
+            "Estimate": { "id": "a", "type": "number", "number": null },
+            "Stage": {
+                "id": "b",
+                "type": "status",
+                "status": { "id": "1", "name": "In progress", "color": "blue" }
+            },
+            "Tags": {
+                "id": "c",
+                "type": "multi_select",
+                "multi_select": [
+                    { "id": "2", "name": "docs", "color": "red" },
+                    { "id": "3", "name": "easy", "color": "green" }
+                ]
+            },
+            "Due": {
+                "id": "d",
+                "type": "date",
+                "date": { "start": "2023-05-01", "end": null, "time_zone": null }
+            },
+            "Blocked by": {
+                "id": "e",
+                "type": "relation",
+                "relation": [{ "id": "7f3c6e28-77fb-4d4c-a2f8-d1a5b7a0a3e1" }],
+                "has_more": false
+            }
+        }
+    }))
+    .unwrap()
+}
+
+#[test]
+fn reads_page_properties() {
+    let task = Task::try_from(&page()).unwrap();
+
+    assert_eq!(
+        task,
+        Task {
+            name: "Write docs".to_owned(),
+            estimate: None,
+            stage: Stage::InProgress,
+            tags: vec!["docs".to_owned(), "easy".to_owned()],
+            due: NaiveDate::from_ymd_opt(2023, 5, 1),
+            blocked_by: vec!["7f3c6e28-77fb-4d4c-a2f8-d1a5b7a0a3e1".to_owned()],
+            notes: String::new(),
+        }
+    );
+}
+
+#[test]
+fn writes_page_properties() {
+    let properties = Task::try_from(&page()).unwrap().to_properties();
+
+    assert_eq!(
+        serde_json::Value::Object(properties),
+        json!({
+            "title": { "title": [{ "type": "text", "text": { "content": "Write docs" } }] },
+            "Estimate": { "number": null },
+            "Stage": { "status": { "name": "In progress" } },
+            "Tags": { "multi_select": [{ "name": "docs" }, { "name": "easy" }] },
+            "Due": { "date": { "start": "2023-05-01" } },
+            "Blocked by": { "relation": [{ "id": "7f3c6e28-77fb-4d4c-a2f8-d1a5b7a0a3e1" }] }
+        })
+    );
+}
+
+#[test]
+fn reports_missing_properties() {
+    let mut page = page();
+    page.properties.remove("Tags");
+
+    assert!(matches!(
+        Task::try_from(&page),
+        Err(notion_client::Error::MissingProperty(name)) if name == "Tags"
+    ));
+}
+
+#[test]
+fn reports_unknown_options() {
+    let mut page = page();
+    page.properties.insert(
+        "Stage".to_owned(),
+        serde_json::from_value(json!({
+            "id": "b",
+            "type": "status",
+            "status": { "id": "4", "name": "Blocked", "color": "red" }
+        }))
+        .unwrap(),
+    );
+
+    assert!(matches!(
+        Task::try_from(&page),
+        Err(notion_client::Error::UnknownOption { property, option })
+            if property == "Stage" && option == "Blocked"
+    ));
+}
+
+#[derive(Debug, PartialEq, NotionPage)]
+struct Points {
+    #[notion(name = "Estimate")]
+    estimate: Option<u32>,
+}
+
+#[test]
+fn reads_only_whole_numbers_into_integers() {
+    let with_estimate = |estimate: f64| {
+        let mut page = page();
+        page.properties.insert(
+            "Estimate".to_owned(),
+            serde_json::from_value(json!({ "id": "a", "type": "number", "number": estimate }))
+                .unwrap(),
+        );
+
+        Points::try_from(&page)
+    };
+
+    assert_eq!(with_estimate(3.0).unwrap(), Points { estimate: Some(3) });
+    for estimate in [3.7, -1.0, 1e10] {
+        assert!(matches!(
+            with_estimate(estimate),
+            Err(notion_client::Error::NumberOutOfRange { property, .. }) if property == "Estimate"
+        ));
+    }
+}
@@ -0,0 +1,812 @@
+//! Exports pages with their block trees, and databases, to a directory.
+//!
+//! The directory holds a `manifest.json` with an entry per object, `pages/<id>.json` with a page
+//! and its blocks (and optionally `pages/<id>.md`), `databases/<id>.json`, and the Notion-hosted
+//! files of every page in `files/<page id>/`. Objects whose `last_edited_time` didn't change since
+//! the last run are skipped.
+
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::path::PathBuf;
+
+use futures_util::io::AllowStdIo;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::files::{DownloadOptions, FileParent};
+use crate::mapping::plain_text;
+use crate::{
+    Block, BlockId, BlockTreeOptions, BlockType, Client, Database, DatabaseId, DatabaseOptions,
+    DatabaseQueryOptions, Error, File, Page, PageId, PageOptions, Parent, Property, Result,
+    SearchAllOptions, SearchResult,
+};
+
+/// Where `Backup::run` finds the objects to export.
+pub enum BackupSource {
+    /// All pages and databases shared with the integration.
+    Search,
+    /// A page, its child pages and databases, and their rows and child pages in turn.
+    Page(PageId),
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BackupManifest {
+    /// Entries by the id of their page or database.
+    pub objects: BTreeMap<String, ManifestEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ManifestEntry {
+    pub object: BackupObject,
+    pub title: String,
+    pub last_edited_time: String,
+    /// The id of the parent page, database or block, `None` for the workspace.
+    pub parent: Option<String>,
+    /// The JSON file, relative to the backup directory.
+    pub path: String,
+    #[serde(default)]
+    pub attachments: Vec<String>,
+    /// Whether all attachments were downloaded, which they aren't with `Backup::attachments` off or
+    /// when a download failed.
+    #[serde(default)]
+    pub downloaded: bool,
+    /// The Markdown rendering of a page, relative to the backup directory.
+    #[serde(default)]
+    pub markdown: Option<String>,
+    /// The child pages and databases of a page, or the rows of a database.
+    #[serde(default)]
+    pub children: Vec<ObjectReference>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupObject {
+    Page,
+    Database,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+pub struct ObjectReference {
+    pub object: BackupObject,
+    pub id: String,
+}
+
+/// The contents of `pages/<id>.json`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PageBackup {
+    pub page: Page,
+    pub blocks: Vec<Block>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BackupReport {
+    pub exported: usize,
+    /// Objects that didn't change since the last run.
+    pub skipped: usize,
+    /// Files downloaded, not counting those already in the directory.
+    pub attachments: usize,
+    /// Files that couldn't be downloaded, which are tried again on the next run.
+    pub failed_attachments: usize,
+}
+
+/// Exported objects between saves of the manifest.
+const SAVE_EVERY: usize = 50;
+
+pub struct Backup {
+    client: Client,
+    directory: PathBuf,
+    attachments: bool,
+    #[cfg(feature = "convert_from_notion")]
+    markdown: bool,
+}
+
+impl Backup {
+    pub fn new(client: Client, directory: impl Into<PathBuf>) -> Backup {
+        Backup {
+            client,
+            directory: directory.into(),
+            attachments: true,
+            #[cfg(feature = "convert_from_notion")]
+            markdown: false,
+        }
+    }
+
+    /// Whether to download the Notion-hosted files of pages, their icons and covers, which is on
+    /// by default. External files are only linked.
+    pub fn attachments(mut self, attachments: bool) -> Self {
+        self.attachments = attachments;
+
+        self
+    }
+
+    /// Whether to also render every page to Markdown.
+    #[cfg(feature = "convert_from_notion")]
+    pub fn markdown(mut self, markdown: bool) -> Self {
+        self.markdown = markdown;
+
+        self
+    }
+
+    /// The manifest of the last run, empty if there was none.
+    pub fn manifest(&self) -> Result<BackupManifest> {
+        match std::fs::read(self.directory.join("manifest.json")) {
+            Ok(manifest) => Ok(serde_json::from_slice(&manifest)?),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                Ok(BackupManifest::default())
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Exports the objects of `source` that changed since the last run.
+    ///
+    /// The manifest is saved regularly and when the run ends, also if it fails, so the next run
+    /// continues where it stopped. Objects that are gone stay in the directory and the manifest.
+    /// Objects the integration can't access (other than the root page) and files that can't be
+    /// downloaded are skipped with a warning.
+    pub async fn run(&self, source: BackupSource) -> Result<BackupReport> {
+        let mut manifest = self.manifest()?;
+        let mut report = BackupReport::default();
+
+        let result = self.export_source(source, &mut manifest, &mut report).await;
+        self.save(&manifest)?;
+
+        result.map(|_| report)
+    }
+
+    async fn export_source(
+        &self,
+        source: BackupSource,
+        manifest: &mut BackupManifest,
+        report: &mut BackupReport,
+    ) -> Result<()> {
+        match source {
+            BackupSource::Search => {
+                let results = self
+                    .client
+                    .search_all(SearchAllOptions {
+                        query: None,
+                        filter: None,
+                        sort: None,
+                    })
+                    .await?;
+
+                // Search finds rows and child pages as well, so nothing is followed
+                for result in results {
+                    match result {
+                        SearchResult::Page(page) => {
+                            let id = page.id.clone();
+                            skip_missing(
+                                self.export_page(page, manifest, report).await,
+                                BackupObject::Page,
+                                &id,
+                            )?;
+                        }
+                        SearchResult::Database(database) => {
+                            let rows = manifest
+                                .objects
+                                .get(&database.id)
+                                .map(|entry| entry.children.clone())
+                                .unwrap_or_default();
+                            self.export_database(database, rows, manifest, report)?;
+                        }
+                        // The rows of data sources are found as pages of their own
+                        SearchResult::DataSource(_) | SearchResult::Unknown(_) => {}
+                    }
+                }
+            }
+            BackupSource::Page(page_id) => {
+                let mut queue = VecDeque::from([ObjectReference {
+                    object: BackupObject::Page,
+                    id: page_id.to_string(),
+                }]);
+                let mut visited = HashSet::new();
+
+                while let Some(reference) = queue.pop_front() {
+                    if !visited.insert(reference.clone()) {
+                        continue;
+                    }
+
+                    let exported = self.export(&reference, manifest, report).await;
+                    // Only the root has to exist
+                    let children = if visited.len() > 1 {
+                        skip_missing(exported, reference.object, &reference.id)?
+                    } else {
+                        Some(exported?)
+                    };
+                    queue.extend(children.into_iter().flatten());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fetches and exports a page or database, and returns the objects below it.
+    async fn export(
+        &self,
+        reference: &ObjectReference,
+        manifest: &mut BackupManifest,
+        report: &mut BackupReport,
+    ) -> Result<Vec<ObjectReference>> {
+        match reference.object {
+            BackupObject::Page => {
+                let page = self
+                    .client
+                    .pages
+                    .clone()
+                    .retrieve(PageOptions {
+                        page_id: PageId::new_unchecked(reference.id.as_str()),
+                    })
+                    .await?;
+
+                self.export_page(page, manifest, report).await
+            }
+            BackupObject::Database => {
+                let database = self
+                    .client
+                    .databases
+                    .retrieve(DatabaseOptions {
+                        database_id: DatabaseId::new_unchecked(reference.id.as_str()),
+                    })
+                    .await?;
+
+                // Editing rows doesn't change the database, so its rows are always listed
+                let mut rows = Vec::new();
+                let mut start_cursor = None;
+                loop {
+                    let results = self
+                        .client
+                        .databases
+                        .query(DatabaseQueryOptions {
+                            database_id: DatabaseId::new_unchecked(reference.id.as_str()),
+                            start_cursor,
+                            ..Default::default()
+                        })
+                        .await?;
+
+                    rows.extend(results.results);
+                    match results.next_cursor {
+                        Some(next_cursor) if results.has_more => start_cursor = Some(next_cursor),
+                        _ => break,
+                    }
+                }
+
+                let references = rows
+                    .iter()
+                    .map(|row| ObjectReference {
+                        object: BackupObject::Page,
+                        id: row.id.clone(),
+                    })
+                    .collect();
+                self.export_database(database, references, manifest, report)?;
+
+                let mut children = Vec::new();
+                for row in rows {
+                    children.extend(self.export_page(row, manifest, report).await?);
+                }
+
+                Ok(children)
+            }
+        }
+    }
+
+    /// Exports a page unless it didn't change, and returns its child pages and databases.
+    async fn export_page(
+        &self,
+        page: Page,
+        manifest: &mut BackupManifest,
+        report: &mut BackupReport,
+    ) -> Result<Vec<ObjectReference>> {
+        let last_edited_time = page.last_edited_time.to_string();
+        if let Some(entry) = manifest.objects.get(&page.id) {
+            if entry.last_edited_time == last_edited_time && self.is_complete(entry) {
+                report.skipped += 1;
+                return Ok(entry.children.clone());
+            }
+        }
+
+        let blocks = self
+            .client
+            .blocks
+            .fetch_tree(BlockTreeOptions {
+                block_id: BlockId::new_unchecked(page.id.as_str()),
+                ..Default::default()
+            })
+            .await?;
+
+        let mut children = Vec::new();
+        collect_children(&blocks, &mut children);
+
+        let (attachments, downloaded) = if self.attachments {
+            self.download_attachments(&page, &blocks, report).await?
+        } else {
+            (Vec::new(), false)
+        };
+
+        let title = page
+            .properties
+            .values()
+            .find_map(|property| match property {
+                Property::Title { title, .. } => Some(plain_text(title)),
+                _ => None,
+            })
+            .unwrap_or_default();
+
+        let markdown = self.write_markdown(&page.id, &title, &blocks)?;
+
+        let path = format!("pages/{}.json", page.id);
+        let entry = ManifestEntry {
+            object: BackupObject::Page,
+            title,
+            last_edited_time,
+            parent: parent_id(&page.parent),
+            path: path.clone(),
+            attachments,
+            downloaded,
+            markdown,
+            children: children.clone(),
+        };
+        let id = page.id.clone();
+        self.write(
+            &path,
+            &serde_json::to_vec_pretty(&PageBackup { page, blocks })?,
+        )?;
+
+        manifest.objects.insert(id, entry);
+        self.exported(manifest, report)?;
+
+        Ok(children)
+    }
+
+    /// Exports a database unless it didn't change, and records its rows.
+    fn export_database(
+        &self,
+        database: Database,
+        rows: Vec<ObjectReference>,
+        manifest: &mut BackupManifest,
+        report: &mut BackupReport,
+    ) -> Result<()> {
+        let last_edited_time = database.last_edited_time.to_string();
+        if let Some(entry) = manifest.objects.get_mut(&database.id) {
+            if entry.last_edited_time == last_edited_time
+                && self.directory.join(&entry.path).exists()
+            {
+                entry.children = rows;
+                report.skipped += 1;
+                return Ok(());
+            }
+        }
+
+        let path = format!("databases/{}.json", database.id);
+        self.write(&path, &serde_json::to_vec_pretty(&database)?)?;
+
+        manifest.objects.insert(
+            database.id.clone(),
+            ManifestEntry {
+                object: BackupObject::Database,
+                title: plain_text(&database.title),
+                last_edited_time,
+                parent: parent_id(&database.parent),
+                path,
+                attachments: Vec::new(),
+                downloaded: false,
+                markdown: None,
+                children: rows,
+            },
+        );
+        self.exported(manifest, report)?;
+
+        Ok(())
+    }
+
+    /// Renders a page to `pages/<id>.md` if `markdown` is on, and returns the path.
+    #[cfg(feature = "convert_from_notion")]
+    fn write_markdown(&self, id: &str, title: &str, blocks: &[Block]) -> Result<Option<String>> {
+        if !self.markdown {
+            return Ok(None);
+        }
+
+        let path = format!("pages/{id}.md");
+        let markdown = format!(
+            "# {title}\n\n{}",
+            crate::markdown::blocks_to_markdown(blocks)
+        );
+        self.write(&path, markdown.as_bytes())?;
+
+        Ok(Some(path))
+    }
+
+    #[cfg(not(feature = "convert_from_notion"))]
+    fn write_markdown(&self, _id: &str, _title: &str, _blocks: &[Block]) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// Whether the files of an unchanged page are there, including those of options that were
+    /// off in earlier runs.
+    fn is_complete(&self, entry: &ManifestEntry) -> bool {
+        #[cfg(feature = "convert_from_notion")]
+        let markdown = !self.markdown
+            || entry
+                .markdown
+                .as_ref()
+                .is_some_and(|path| self.directory.join(path).exists());
+        #[cfg(not(feature = "convert_from_notion"))]
+        let markdown = true;
+
+        let attachments = !self.attachments
+            || (entry.downloaded
+                && entry
+                    .attachments
+                    .iter()
+                    .all(|path| self.directory.join(path).exists()));
+
+        self.directory.join(&entry.path).exists() && markdown && attachments
+    }
+
+    /// Counts an exported object, saving the manifest every `SAVE_EVERY` objects.
+    fn exported(&self, manifest: &BackupManifest, report: &mut BackupReport) -> Result<()> {
+        report.exported += 1;
+        if report.exported.is_multiple_of(SAVE_EVERY) {
+            self.save(manifest)?;
+        }
+
+        Ok(())
+    }
+
+    /// Downloads the Notion-hosted files of a page and its blocks to `files/<page id>/`, and returns
+    /// their paths and whether all of them were downloaded. Files are named after the object they
+    /// belong to and their URL, so those already downloaded are kept.
+    async fn download_attachments(
+        &self,
+        page: &Page,
+        blocks: &[Block],
+        report: &mut BackupReport,
+    ) -> Result<(Vec<String>, bool)> {
+        let mut files = Vec::new();
+        if let Some(icon) = page.icon.as_ref().and_then(|icon| icon.file()) {
+            files.push((
+                "icon".to_owned(),
+                FileParent::Page(PageId::new_unchecked(page.id.as_str())),
+                icon,
+            ));
+        }
+        if let Some(cover) = &page.cover {
+            files.push((
+                "cover".to_owned(),
+                FileParent::Page(PageId::new_unchecked(page.id.as_str())),
+                cover.clone(),
+            ));
+        }
+        let mut properties = page.properties.values().collect::<Vec<_>>();
+        properties.sort_by_key(|property| property.id());
+        for property in properties {
+            if let Property::Files {
+                id,
+                files: property,
+            } = property
+            {
+                for (index, file) in property.iter().enumerate() {
+                    files.push((
+                        format!("{id}-{index}"),
+                        FileParent::Page(PageId::new_unchecked(page.id.as_str())),
+                        file.clone(),
+                    ));
+                }
+            }
+        }
+        collect_files(blocks, &mut files);
+
+        let mut paths = Vec::new();
+        let mut downloaded = true;
+        for (key, parent, file) in files {
+            let File::File { file: hosted } = &file else {
+                continue;
+            };
+            let name = hosted
+                .url
+                .split(['?', '#'])
+                .next()
+                .and_then(|url| url.rsplit('/').next())
+                .unwrap_or_default();
+            let path = format!("files/{}/{}", page.id, sanitize(&format!("{key}-{name}")));
+
+            if !self.directory.join(&path).exists() {
+                // Streamed to disk, as attachments can be far larger than the pages around them
+                let (target, temporary) = self.temporary_path(&path)?;
+                let mut writer = AllowStdIo::new(std::fs::File::create(&temporary)?);
+                let download = self
+                    .client
+                    .download(
+                        DownloadOptions {
+                            file: &file,
+                            parent,
+                        },
+                        &mut writer,
+                    )
+                    .await;
+                drop(writer);
+                if let Err(error) = download {
+                    log::warn!("Skipping {} of page {}: {error}", hosted.url, page.id);
+                    std::fs::remove_file(&temporary)?;
+                    report.failed_attachments += 1;
+                    downloaded = false;
+                    continue;
+                }
+
+                std::fs::rename(temporary, target)?;
+                report.attachments += 1;
+            }
+
+            paths.push(path);
+        }
+
+        Ok((paths, downloaded))
+    }
+
+    fn save(&self, manifest: &BackupManifest) -> Result<()> {
+        self.write("manifest.json", &serde_json::to_vec_pretty(manifest)?)
+    }
+
+    /// Writes to a temporary file that replaces `path` once complete, so that an interrupted run
+    /// never leaves a truncated file behind.
+    fn write(&self, path: &str, contents: &[u8]) -> Result<()> {
+        let (path, temporary) = self.temporary_path(path)?;
+        std::fs::write(&temporary, contents)?;
+
+        Ok(std::fs::rename(temporary, path)?)
+    }
+
+    /// Returns `path` in the backup and the temporary file next to it, creating their directory.
+    fn temporary_path(&self, path: &str) -> Result<(PathBuf, PathBuf)> {
+        let path = self.directory.join(path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut temporary = path.clone().into_os_string();
+        temporary.push(".tmp");
+
+        Ok((path, temporary.into()))
+    }
+}
+
+fn collect_children(blocks: &[Block], children: &mut Vec<ObjectReference>) {
+    for block in blocks {
+        match block.block {
+            BlockType::ChildPage { .. } => children.push(ObjectReference {
+                object: BackupObject::Page,
+                id: block.id.clone(),
+            }),
+            BlockType::ChildDatabase { .. } => children.push(ObjectReference {
+                object: BackupObject::Database,
+                id: block.id.clone(),
+            }),
+            _ => {}
+        }
+
+        if let Some(nested) = block.block.children() {
+            collect_children(nested, children);
+        }
+    }
+}
+
+fn collect_files(blocks: &[Block], files: &mut Vec<(String, FileParent, File)>) {
+    for block in blocks {
+        if let Some(file) = block.block.file() {
+            files.push((
+                block.id.clone(),
+                FileParent::Block(BlockId::new_unchecked(block.id.as_str())),
+                file.clone(),
+            ));
+        }
+
+        if let Some(nested) = block.block.children() {
+            collect_files(nested, files);
+        }
+    }
+}
+
+fn parent_id(parent: &Parent) -> Option<String> {
+    match parent {
+        Parent::PageId { page_id } => Some(page_id.clone()),
+        Parent::DatabaseId { database_id } => Some(database_id.clone()),
+        Parent::DataSourceId {
+            data_source_id,
+            database_id,
+        } => Some(database_id.as_ref().unwrap_or(data_source_id).clone()),
+        Parent::BlockId { block_id } => Some(block_id.clone()),
+        Parent::Workspace => None,
+    }
+}
+
+/// Turns the error of an object the integration can't access into a warning.
+fn skip_missing<T>(result: Result<T>, object: BackupObject, id: &str) -> Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(Error::Http(error, _)) if error.status() == Some(StatusCode::NOT_FOUND) => {
+            log::warn!("Skipping {object:?} {id}: {error}");
+            Ok(None)
+        }
+        Err(error) => Err(error),
+    }
+}
+
+/// Keeps file names portable.
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|character| match character {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' | '_' => character,
+            _ => '_',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    const PAGE_ID: &str = "59833787-2cf9-4fdf-8782-e53db20768a5";
+
+    fn client(block_requests: Arc<AtomicUsize>, files_available: bool) -> Client {
+        Client::new()
+            .api_key("secret")
+            .custom_request(move |request| {
+                let block_requests = block_requests.clone();
+
+                Box::pin(async move {
+                    let request = request.try_clone().unwrap().build()?;
+                    let url = request.url();
+
+                    let body = if url.path() == "/v1/search" {
+                        json!({
+                            "object": "list",
+                            "results": [{
+                                "object": "page",
+                                "id": PAGE_ID,
+                                "created_by": { "id": "user" },
+                                "url": format!("https://www.notion.so/{PAGE_ID}"),
+                                "parent": { "type": "workspace", "workspace": true },
+                                "created_time": "2024-01-01T00:00:00.000Z",
+                                "last_edited_time": "2024-01-02T00:00:00.000Z",
+                                "cover": null,
+                                "icon": null,
+                                "archived": false,
+                                "properties": {
+                                    "Name": { "id": "title", "type": "title", "title": [] }
+                                }
+                            }],
+                            "next_cursor": null,
+                            "has_more": false
+                        })
+                        .to_string()
+                    } else if url.path().ends_with("/children") {
+                        block_requests.fetch_add(1, Ordering::SeqCst);
+                        json!({
+                            "object": "list",
+                            "results": [{
+                                "object": "block",
+                                "id": "block",
+                                "parent": { "type": "page_id", "page_id": PAGE_ID },
+                                "created_time": "2024-01-01T00:00:00.000Z",
+                                "last_edited_time": "2024-01-01T00:00:00.000Z",
+                                "created_by": { "object": "user", "id": "user" },
+                                "last_edited_by": { "object": "user", "id": "user" },
+                                "has_children": false,
+                                "archived": false,
+                                "type": "image",
+                                "image": {
+                                    "type": "file",
+                                    "file": {
+                                        "url": "https://prod-files-secure.s3.us-west-2.amazonaws.com/workspace/image/chart.png?X-Amz-Signature=1",
+                                        "expiry_time": "2099-01-01T00:00:00.000Z"
+                                    }
+                                }
+                            }],
+                            "next_cursor": null,
+                            "has_more": false
+                        })
+                        .to_string()
+                    } else if files_available {
+                        "PNG".to_owned()
+                    } else {
+                        let mut response = http::Response::new("Not Found".to_owned());
+                        *response.status_mut() = http::StatusCode::NOT_FOUND;
+
+                        return Ok(reqwest::Response::from(response));
+                    };
+
+                    Ok(reqwest::Response::from(http::Response::new(body)))
+                })
+            })
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn unchanged_pages_are_skipped() {
+        let directory = std::env::temp_dir().join(format!("notion-backup-{}", std::process::id()));
+        let block_requests = Arc::new(AtomicUsize::new(0));
+        let backup = Backup::new(client(block_requests.clone(), true), &directory);
+
+        let report = backup.run(BackupSource::Search).await.unwrap();
+        assert_eq!(
+            report,
+            BackupReport {
+                exported: 1,
+                skipped: 0,
+                attachments: 1,
+                failed_attachments: 0
+            }
+        );
+        let entry = &backup.manifest().unwrap().objects[PAGE_ID];
+        assert_eq!(entry.parent, None);
+        assert_eq!(
+            entry.attachments,
+            [format!("files/{PAGE_ID}/block-chart.png")]
+        );
+        assert_eq!(
+            std::fs::read(directory.join(&entry.attachments[0])).unwrap(),
+            b"PNG"
+        );
+        let exported: PageBackup =
+            serde_json::from_slice(&std::fs::read(directory.join(&entry.path)).unwrap()).unwrap();
+        assert_eq!(exported.blocks.len(), 1);
+
+        let report = backup.run(BackupSource::Search).await.unwrap();
+        assert_eq!((report.exported, report.skipped), (0, 1));
+        assert_eq!(block_requests.load(Ordering::SeqCst), 1);
+
+        // Options turned on later apply to unchanged pages too
+        #[cfg(feature = "convert_from_notion")]
+        {
+            let backup =
+                Backup::new(client(block_requests.clone(), true), &directory).markdown(true);
+            let report = backup.run(BackupSource::Search).await.unwrap();
+            assert_eq!(report.exported, 1);
+            assert!(directory.join(format!("pages/{PAGE_ID}.md")).exists());
+        }
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn failed_downloads_are_retried() {
+        let directory =
+            std::env::temp_dir().join(format!("notion-backup-failed-{}", std::process::id()));
+        let block_requests = Arc::new(AtomicUsize::new(0));
+
+        let backup = Backup::new(client(block_requests.clone(), false), &directory);
+        let report = backup.run(BackupSource::Search).await.unwrap();
+        assert_eq!((report.exported, report.failed_attachments), (1, 1));
+        assert!(!backup.manifest().unwrap().objects[PAGE_ID].downloaded);
+        // Nothing of the failed download is left behind
+        assert_eq!(
+            std::fs::read_dir(directory.join(format!("files/{PAGE_ID}")))
+                .unwrap()
+                .count(),
+            0
+        );
+
+        let backup = Backup::new(client(block_requests.clone(), true), &directory);
+        let report = backup.run(BackupSource::Search).await.unwrap();
+        assert_eq!((report.exported, report.attachments), (1, 1));
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn file_names_are_sanitized() {
+        assert_eq!(sanitize("a%3Bc-0-Q1 report.pdf"), "a_3Bc-0-Q1_report.pdf");
+        assert_eq!(
+            parent_id(&Parent::DataSourceId {
+                data_source_id: "source".to_owned(),
+                database_id: Some("database".to_owned())
+            })
+            .as_deref(),
+            Some("database")
+        );
+    }
+}
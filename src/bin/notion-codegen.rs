@@ -0,0 +1,67 @@
+//! Generates Rust types for a database, either from a recorded schema or from the live API.
+//!
+//! ```text
+//! notion-codegen --file database.json [--name Tasks]
+//! NOTION_API_KEY=secret_... notion-codegen --database-id <id> [--name Tasks]
+//! ```
+
+use notion_client::codegen::{generate_database_types, type_ident};
+use notion_client::{Client, Database, DatabaseId, DatabaseOptions};
+
+const USAGE: &str =
+    "usage: notion-codegen (--file <database.json> | --database-id <id>) [--name <StructName>]";
+
+#[tokio::main]
+async fn main() {
+    let mut file = None;
+    let mut database_id = None;
+    let mut name = None;
+
+    let mut arguments = std::env::args().skip(1);
+    while let Some(argument) = arguments.next() {
+        let value = arguments.next().unwrap_or_else(|| exit(USAGE));
+
+        match argument.as_str() {
+            "--file" => file = Some(value),
+            "--database-id" => database_id = Some(value),
+            "--name" => name = Some(value),
+            _ => exit(USAGE),
+        }
+    }
+
+    let database = match (file, database_id) {
+        (Some(file), None) => {
+            let json = std::fs::read_to_string(&file)
+                .unwrap_or_else(|error| exit(&format!("could not read {file}: {error}")));
+
+            serde_json::from_str::<Database>(&json)
+                .unwrap_or_else(|error| exit(&format!("could not parse {file}: {error}")))
+        }
+        (None, Some(database_id)) => {
+            let id = DatabaseId::parse(&database_id)
+                .unwrap_or_else(|_| exit(&format!("{database_id} is not a database id or URL")));
+            let api_key = std::env::var("NOTION_API_KEY")
+                .unwrap_or_else(|_| exit("NOTION_API_KEY needs to be set to fetch a database"));
+
+            Client::new()
+                .api_key(&api_key)
+                .build()
+                .unwrap_or_else(|error| exit(&format!("could not build client: {error}")))
+                .databases
+                .retrieve(DatabaseOptions { database_id: id })
+                .await
+                .unwrap_or_else(|error| exit(&format!("could not fetch {database_id}: {error}")))
+        }
+        _ => exit(USAGE),
+    };
+
+    let name =
+        name.unwrap_or_else(|| type_ident(&notion_client::mapping::plain_text(&database.title)));
+
+    print!("{}", generate_database_types(&name, &database));
+}
+
+fn exit(message: &str) -> ! {
+    eprintln!("{message}");
+    std::process::exit(1)
+}
@@ -0,0 +1,307 @@
+//! A synchronous client for programs without an async runtime.
+//!
+//! Every call runs the request of the async client to completion on an internal single threaded
+//! runtime, so it must not be used from within an async runtime.
+
+use std::sync::Arc;
+
+use futures_util::io::AllowStdIo;
+use tokio::runtime::Runtime;
+
+use crate::files::DownloadOptions;
+use crate::patch::BlockOperation;
+use crate::{
+    Block, BlockChildrenAppendOptions, BlockChildrenListOptions, BlockOptions, BlockTreeOptions,
+    BlockUpdateOptions, Comment, CommentCreateOptions, CommentListOptions, Database,
+    DatabaseOptions, DatabaseQueryOptions, Error, FileUpload, FileUploadContentOptions,
+    FileUploadCreateOptions, FileUploadOptions, FileUploadSendOptions, Page, PageOptions,
+    PageProperty, PagePropertyOptions, QueryResponse, Result, SearchAllOptions, SearchOptions,
+    SearchResult, User, UserOptions,
+};
+
+#[derive(Clone)]
+pub struct Client {
+    runtime: Arc<Runtime>,
+    client: crate::Client,
+
+    pub pages: Pages,
+    pub blocks: Blocks,
+    pub databases: Databases,
+    pub users: Users,
+    pub comments: Comments,
+    pub file_uploads: FileUploads,
+}
+
+impl Client {
+    /// Configure the client like an async one, then finish with `ClientBuilder::build_blocking`.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new() -> crate::ClientBuilder {
+        crate::ClientBuilder::default()
+    }
+
+    pub(crate) fn from_async(client: crate::Client) -> Result<Client> {
+        let runtime = Arc::new(
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(Error::Io)?,
+        );
+
+        Ok(Client {
+            pages: Pages {
+                runtime: runtime.clone(),
+                pages: client.pages.clone(),
+            },
+            blocks: Blocks {
+                runtime: runtime.clone(),
+                blocks: client.blocks.clone(),
+            },
+            databases: Databases {
+                runtime: runtime.clone(),
+                databases: client.databases.clone(),
+            },
+            users: Users {
+                runtime: runtime.clone(),
+                users: client.users.clone(),
+            },
+            comments: Comments {
+                runtime: runtime.clone(),
+                comments: client.comments.clone(),
+            },
+            file_uploads: FileUploads {
+                runtime: runtime.clone(),
+                file_uploads: client.file_uploads.clone(),
+            },
+            runtime,
+            client,
+        })
+    }
+
+    pub fn search<'b, T: std::fmt::Debug + for<'de> serde::Deserialize<'de>>(
+        &self,
+        options: SearchOptions<'b>,
+    ) -> Result<QueryResponse<T>> {
+        self.runtime.block_on(self.client.clone().search(options))
+    }
+
+    pub fn search_all(&self, options: SearchAllOptions) -> Result<Vec<SearchResult>> {
+        self.runtime.block_on(self.client.search_all(options))
+    }
+
+    /// See `crate::Client::download`.
+    pub fn download<W: std::io::Write>(
+        &self,
+        options: DownloadOptions,
+        writer: &mut W,
+    ) -> Result<u64> {
+        self.runtime
+            .block_on(self.client.download(options, &mut AllowStdIo::new(writer)))
+    }
+}
+
+#[derive(Clone)]
+pub struct Pages {
+    runtime: Arc<Runtime>,
+    pages: crate::Pages,
+}
+
+impl Pages {
+    pub fn retrieve(&self, options: PageOptions) -> Result<Page> {
+        self.runtime.block_on(self.pages.clone().retrieve(options))
+    }
+
+    pub fn property(&self, options: PagePropertyOptions) -> Result<PageProperty> {
+        self.runtime.block_on(self.pages.property(options))
+    }
+}
+
+#[derive(Clone)]
+pub struct Blocks {
+    runtime: Arc<Runtime>,
+    blocks: crate::Blocks,
+}
+
+impl Blocks {
+    pub fn retrieve(&self, options: BlockOptions) -> Result<Block> {
+        self.runtime.block_on(self.blocks.retrieve(options))
+    }
+
+    pub fn update(&self, options: BlockUpdateOptions) -> Result<Block> {
+        self.runtime.block_on(self.blocks.update(options))
+    }
+
+    pub fn delete(&self, options: BlockOptions) -> Result<Block> {
+        self.runtime.block_on(self.blocks.delete(options))
+    }
+
+    pub fn children(&self) -> BlockChildren {
+        BlockChildren {
+            runtime: self.runtime.clone(),
+            blocks: self.blocks.clone(),
+        }
+    }
+
+    pub fn fetch_tree(&self, options: BlockTreeOptions) -> Result<Vec<Block>> {
+        self.runtime.block_on(self.blocks.fetch_tree(options))
+    }
+
+    pub fn apply(&self, operations: Vec<BlockOperation>) -> Result<()> {
+        self.runtime.block_on(self.blocks.apply(operations))
+    }
+}
+
+pub struct BlockChildren {
+    runtime: Arc<Runtime>,
+    blocks: crate::Blocks,
+}
+
+impl BlockChildren {
+    pub fn list(&self, options: BlockChildrenListOptions) -> Result<QueryResponse<Block>> {
+        self.runtime.block_on(self.blocks.children().list(options))
+    }
+
+    pub fn append(&self, options: BlockChildrenAppendOptions) -> Result<Vec<Block>> {
+        self.runtime
+            .block_on(self.blocks.children().append(options))
+    }
+}
+
+#[derive(Clone)]
+pub struct Databases {
+    runtime: Arc<Runtime>,
+    databases: crate::Databases,
+}
+
+impl Databases {
+    pub fn retrieve(&self, options: DatabaseOptions) -> Result<Database> {
+        self.runtime.block_on(self.databases.retrieve(options))
+    }
+
+    pub fn query(&self, options: DatabaseQueryOptions) -> Result<QueryResponse<Page>> {
+        self.runtime.block_on(self.databases.query(options))
+    }
+}
+
+#[derive(Clone)]
+pub struct Users {
+    runtime: Arc<Runtime>,
+    users: crate::Users,
+}
+
+impl Users {
+    pub fn get(&self) -> Result<QueryResponse<User>> {
+        self.runtime.block_on(self.users.get())
+    }
+
+    pub fn retrieve(&self, options: UserOptions) -> Result<User> {
+        self.runtime.block_on(self.users.retrieve(options))
+    }
+
+    pub fn me(&self) -> Result<User> {
+        self.runtime.block_on(self.users.me())
+    }
+}
+
+#[derive(Clone)]
+pub struct Comments {
+    runtime: Arc<Runtime>,
+    comments: crate::Comments,
+}
+
+impl Comments {
+    pub fn list(&self, options: CommentListOptions) -> Result<Vec<Comment>> {
+        self.runtime.block_on(self.comments.list(options))
+    }
+
+    pub fn create(&self, options: CommentCreateOptions) -> Result<Comment> {
+        self.runtime.block_on(self.comments.create(options))
+    }
+}
+
+#[derive(Clone)]
+pub struct FileUploads {
+    runtime: Arc<Runtime>,
+    file_uploads: crate::FileUploads,
+}
+
+impl FileUploads {
+    pub fn upload(&self, options: FileUploadContentOptions) -> Result<FileUpload> {
+        self.runtime.block_on(self.file_uploads.upload(options))
+    }
+
+    pub fn create(&self, options: FileUploadCreateOptions) -> Result<FileUpload> {
+        self.runtime.block_on(self.file_uploads.create(options))
+    }
+
+    pub fn send(&self, options: FileUploadSendOptions) -> Result<FileUpload> {
+        self.runtime.block_on(self.file_uploads.send(options))
+    }
+
+    pub fn complete(&self, options: FileUploadOptions) -> Result<FileUpload> {
+        self.runtime.block_on(self.file_uploads.complete(options))
+    }
+
+    pub fn retrieve(&self, options: FileUploadOptions) -> Result<FileUpload> {
+        self.runtime.block_on(self.file_uploads.retrieve(options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn requests_run_without_a_runtime() {
+        let client = Client::new()
+            .api_key("secret")
+            .custom_request(|request| {
+                Box::pin(async move {
+                    let request = request.try_clone().unwrap().build()?;
+                    assert_eq!(request.url().path(), "/v1/users/me");
+                    let body = json!({ "object": "user", "id": "bot", "type": "bot", "bot": {} });
+
+                    Ok(reqwest::Response::from(http::Response::new(
+                        body.to_string(),
+                    )))
+                })
+            })
+            .build_blocking()
+            .unwrap();
+
+        assert_eq!(client.users.me().unwrap().id(), "bot");
+    }
+
+    #[test]
+    fn downloads_write_into_std_writers() {
+        let client = Client::new()
+            .api_key("secret")
+            .custom_request(|request| {
+                Box::pin(async move {
+                    let request = request.try_clone().unwrap().build()?;
+                    assert_eq!(request.url().as_str(), "https://example.com/chart.png");
+
+                    Ok(reqwest::Response::from(http::Response::new("PNG")))
+                })
+            })
+            .build_blocking()
+            .unwrap();
+        let file = serde_json::from_value::<crate::File>(json!({
+            "type": "external",
+            "external": { "url": "https://example.com/chart.png" }
+        }))
+        .unwrap();
+
+        let mut bytes = Vec::new();
+        let written = client
+            .download(
+                DownloadOptions {
+                    file: &file,
+                    parent: crate::files::FileParent::Block(crate::BlockId::new_unchecked("block")),
+                },
+                &mut bytes,
+            )
+            .unwrap();
+        assert_eq!((written, bytes), (3, b"PNG".to_vec()));
+    }
+}
@@ -0,0 +1,547 @@
+//! Generates Rust types for the pages of a database out of its schema.
+//!
+//! The generated code derives `NotionPage`/`NotionSelect`, so it needs the `derive` feature.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::{Database, DatabaseFormulaType, DatabaseProperty, SelectOption};
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where", "while",
+    "abstract", "become", "box", "do", "final", "gen", "macro", "override", "priv", "try",
+    "typeof", "unsized", "virtual", "yield",
+];
+
+struct Field {
+    ident: String,
+    ty: String,
+    name: String,
+    id: String,
+    kind: Option<&'static str>,
+    read_only: bool,
+    options: Option<(String, &'static str)>,
+}
+
+/// Generates a `struct_name` struct with a field per supported property of `database`, an enum
+/// per select, multi select and status property, and a `<struct_name>Filter` with helpers that
+/// build filter JSON for each property.
+pub fn generate_database_types(struct_name: &str, database: &Database) -> String {
+    let mut properties = database.properties.iter().collect::<Vec<_>>();
+    properties.sort_by_key(|(name, _)| *name);
+
+    let mut output = String::new();
+    let mut enums = String::new();
+    let mut fields = Vec::new();
+    let mut unsupported = Vec::new();
+    let mut idents = HashSet::new();
+    // Enums share a namespace with the struct and its filter
+    let mut types = HashSet::from([struct_name.to_owned(), format!("{struct_name}Filter")]);
+
+    for (name, property) in properties {
+        let Some(id) = property.id() else {
+            unsupported.push(name.to_owned());
+            continue;
+        };
+
+        let ident = unique(field_ident(name), &mut idents);
+        let enum_name = match property {
+            DatabaseProperty::MultiSelect { .. }
+            | DatabaseProperty::Select { .. }
+            | DatabaseProperty::Status { .. } => {
+                unique(format!("{struct_name}{}", type_ident(name)), &mut types)
+            }
+            _ => String::new(),
+        };
+
+        let (ty, kind, read_only) = match property {
+            DatabaseProperty::Checkbox { .. } => ("bool".to_owned(), None, false),
+            DatabaseProperty::CreatedTime { .. } | DatabaseProperty::LastEditedTime { .. } => {
+                ("chrono::DateTime<chrono::Utc>".to_owned(), None, true)
+            }
+            DatabaseProperty::Date { .. } => {
+                ("Option<notion_client::Date>".to_owned(), None, false)
+            }
+            DatabaseProperty::Email { .. } => ("Option<String>".to_owned(), Some("email"), false),
+            DatabaseProperty::Formula { formula, .. } => {
+                let ty = match formula.suspected_type {
+                    Some(DatabaseFormulaType::Boolean) => "Option<bool>",
+                    Some(DatabaseFormulaType::Date) => "Option<notion_client::Date>",
+                    Some(DatabaseFormulaType::Number) => "Option<f64>",
+                    Some(DatabaseFormulaType::String) | None => "Option<String>",
+                };
+
+                (ty.to_owned(), None, true)
+            }
+            DatabaseProperty::MultiSelect { multi_select, .. } => {
+                let ty = write_enum(&mut enums, &enum_name, &multi_select.options);
+                (format!("Vec<{ty}>"), None, false)
+            }
+            DatabaseProperty::Number { .. } => ("Option<f64>".to_owned(), None, false),
+            DatabaseProperty::People { .. } => ("Vec<String>".to_owned(), Some("people"), false),
+            DatabaseProperty::PhoneNumber { .. } => {
+                ("Option<String>".to_owned(), Some("phone_number"), false)
+            }
+            DatabaseProperty::Relation { .. } => {
+                ("Vec<String>".to_owned(), Some("relation"), false)
+            }
+            DatabaseProperty::RichText { .. } => ("String".to_owned(), Some("rich_text"), false),
+            DatabaseProperty::Select { select, .. } => {
+                let ty = write_enum(&mut enums, &enum_name, &select.options);
+                (format!("Option<{ty}>"), Some("select"), false)
+            }
+            DatabaseProperty::Status { status, .. } => {
+                let ty = write_enum(&mut enums, &enum_name, &status.options);
+                (format!("Option<{ty}>"), Some("status"), false)
+            }
+            DatabaseProperty::Title { .. } => ("String".to_owned(), Some("title"), false),
+            DatabaseProperty::Url { .. } => ("Option<String>".to_owned(), Some("url"), false),
+            DatabaseProperty::Files { .. }
+            | DatabaseProperty::LastEditedBy { .. }
+            | DatabaseProperty::Rollup { .. }
+            | DatabaseProperty::Unsupported(..) => {
+                unsupported.push(name.to_owned());
+                continue;
+            }
+        };
+
+        // Option enums also get a typed filter helper
+        let options = match property {
+            DatabaseProperty::MultiSelect { .. } if ty != "Vec<String>" => {
+                Some((enum_name, "contains"))
+            }
+            DatabaseProperty::Select { .. } | DatabaseProperty::Status { .. }
+                if ty != "Option<String>" =>
+            {
+                Some((enum_name, "equals"))
+            }
+            _ => None,
+        };
+
+        fields.push(Field {
+            ident,
+            ty,
+            name: name.to_owned(),
+            id,
+            kind,
+            read_only,
+            options,
+        });
+    }
+
+    writeln!(
+        output,
+        "// Generated by notion-codegen from database {:?} ({}), do not edit.",
+        plain_title(database),
+        database.id
+    )
+    .unwrap();
+    writeln!(output).unwrap();
+    writeln!(output, "use notion_client::mapping::{{json, Value}};").unwrap();
+    writeln!(output, "use notion_client::{{NotionPage, NotionSelect}};").unwrap();
+    output.push_str(&enums);
+    writeln!(output).unwrap();
+
+    writeln!(output, "#[derive(Debug, Clone, PartialEq, NotionPage)]").unwrap();
+    writeln!(output, "pub struct {struct_name} {{").unwrap();
+    for field in &fields {
+        let mut attributes = vec![
+            format!("id = {:?}", field.id),
+            format!("name = {:?}", field.name),
+        ];
+        if let Some(kind) = field.kind {
+            attributes.push(format!("kind = {kind:?}"));
+        }
+        if field.read_only {
+            attributes.push("read_only".to_owned());
+        }
+
+        writeln!(output, "    #[notion({})]", attributes.join(", ")).unwrap();
+        writeln!(output, "    pub {}: {},", field.ident, field.ty).unwrap();
+    }
+    for name in &unsupported {
+        writeln!(output, "    // {name:?} is not supported yet").unwrap();
+    }
+    writeln!(output, "}}").unwrap();
+    writeln!(output).unwrap();
+
+    writeln!(output, "pub struct {struct_name}Filter;").unwrap();
+    writeln!(output).unwrap();
+    writeln!(output, "impl {struct_name}Filter {{").unwrap();
+    for (index, field) in fields.iter().enumerate() {
+        let condition = database
+            .properties
+            .get(&field.name)
+            .map(filter_condition)
+            .unwrap_or_else(|| "\"rich_text\": { condition: value.into() }".to_owned());
+
+        if index > 0 {
+            writeln!(output).unwrap();
+        }
+        writeln!(
+            output,
+            "    /// Filters on {:?}, e.g. `{}(\"equals\", ...)`.",
+            field.name, field.ident
+        )
+        .unwrap();
+        writeln!(
+            output,
+            "    pub fn {}(condition: &str, value: impl Into<Value>) -> Value {{",
+            field.ident
+        )
+        .unwrap();
+        writeln!(
+            output,
+            "        json!({{ \"property\": {:?}, {condition} }})",
+            field.id
+        )
+        .unwrap();
+        writeln!(output, "    }}").unwrap();
+
+        if let Some((enum_name, condition)) = &field.options {
+            let ident = field.ident.trim_start_matches("r#");
+
+            writeln!(output).unwrap();
+            writeln!(
+                output,
+                "    pub fn {ident}_{condition}(option: {enum_name}) -> Value {{"
+            )
+            .unwrap();
+            writeln!(
+                output,
+                "        Self::{}({condition:?}, notion_client::mapping::SelectValue::option_name(&option))",
+                field.ident
+            )
+            .unwrap();
+            writeln!(output, "    }}").unwrap();
+        }
+    }
+    writeln!(output, "}}").unwrap();
+
+    output
+}
+
+/// The condition of a filter on `property`, which formulas nest under the type of their result.
+fn filter_condition(property: &DatabaseProperty) -> String {
+    match property {
+        DatabaseProperty::Formula { formula, .. } => {
+            let kind = match formula.suspected_type {
+                Some(DatabaseFormulaType::Boolean) => "checkbox",
+                Some(DatabaseFormulaType::Date) => "date",
+                Some(DatabaseFormulaType::Number) => "number",
+                Some(DatabaseFormulaType::String) | None => "string",
+            };
+
+            format!("\"formula\": {{ {kind:?}: {{ condition: value.into() }} }}")
+        }
+        _ => format!("{:?}: {{ condition: value.into() }}", filter_kind(property)),
+    }
+}
+
+fn filter_kind(property: &DatabaseProperty) -> &'static str {
+    match property {
+        DatabaseProperty::Checkbox { .. } => "checkbox",
+        DatabaseProperty::CreatedTime { .. } => "created_time",
+        DatabaseProperty::Date { .. } => "date",
+        DatabaseProperty::Email { .. } => "email",
+        DatabaseProperty::Files { .. } => "files",
+        DatabaseProperty::Formula { .. } => "formula",
+        DatabaseProperty::LastEditedBy { .. } => "last_edited_by",
+        DatabaseProperty::LastEditedTime { .. } => "last_edited_time",
+        DatabaseProperty::MultiSelect { .. } => "multi_select",
+        DatabaseProperty::Number { .. } => "number",
+        DatabaseProperty::People { .. } => "people",
+        DatabaseProperty::PhoneNumber { .. } => "phone_number",
+        DatabaseProperty::Relation { .. } => "relation",
+        DatabaseProperty::RichText { .. } => "rich_text",
+        DatabaseProperty::Rollup { .. } => "rollup",
+        DatabaseProperty::Select { .. } => "select",
+        DatabaseProperty::Status { .. } => "status",
+        DatabaseProperty::Title { .. } => "title",
+        DatabaseProperty::Url { .. } => "url",
+        DatabaseProperty::Unsupported(..) => "rich_text",
+    }
+}
+
+/// Writes an enum of `options` and returns the type to use for a single option, which is a plain
+/// `String` when there are no options to speak of yet.
+fn write_enum(output: &mut String, enum_name: &str, options: &[SelectOption]) -> String {
+    if options.is_empty() {
+        return "String".to_owned();
+    }
+
+    let mut idents = HashSet::new();
+    let variants = options
+        .iter()
+        .map(|option| (unique(type_ident(&option.name), &mut idents), option))
+        .collect::<Vec<_>>();
+
+    writeln!(output).unwrap();
+    writeln!(
+        output,
+        "#[derive(Debug, Clone, Copy, PartialEq, Eq, NotionSelect)]"
+    )
+    .unwrap();
+    writeln!(output, "pub enum {enum_name} {{").unwrap();
+    for (ident, option) in &variants {
+        writeln!(output, "    #[notion(name = {:?})]", option.name).unwrap();
+        writeln!(output, "    {ident},").unwrap();
+    }
+    writeln!(output, "}}").unwrap();
+    writeln!(output).unwrap();
+    writeln!(output, "impl {enum_name} {{").unwrap();
+    writeln!(output, "    pub fn color(&self) -> notion_client::Color {{").unwrap();
+    writeln!(output, "        match self {{").unwrap();
+    for (ident, option) in &variants {
+        writeln!(
+            output,
+            "            Self::{ident} => notion_client::Color::{:?},",
+            option.color
+        )
+        .unwrap();
+    }
+    writeln!(output, "        }}").unwrap();
+    writeln!(output, "    }}").unwrap();
+    writeln!(output, "}}").unwrap();
+
+    enum_name.to_owned()
+}
+
+fn plain_title(database: &Database) -> String {
+    crate::mapping::plain_text(&database.title)
+}
+
+fn words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+
+    for character in name.chars() {
+        if character.is_alphanumeric() {
+            // Split camelCase too
+            if character.is_uppercase()
+                && word.chars().last().is_some_and(|last| last.is_lowercase())
+            {
+                words.push(std::mem::take(&mut word));
+            }
+            word.push(character);
+        } else if !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+fn field_ident(name: &str) -> String {
+    let ident = words(name)
+        .iter()
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join("_");
+
+    match ident.as_str() {
+        "" => "property".to_owned(),
+        "self" | "crate" | "super" => format!("{ident}_"),
+        _ if ident.starts_with(|character: char| character.is_ascii_digit()) => {
+            format!("_{ident}")
+        }
+        _ if KEYWORDS.contains(&ident.as_str()) => format!("r#{ident}"),
+        _ => ident,
+    }
+}
+
+/// Turns a property or option name into a PascalCase identifier.
+pub fn type_ident(name: &str) -> String {
+    let ident = words(name)
+        .iter()
+        .map(|word| {
+            let mut characters = word.chars();
+            characters
+                .next()
+                .map(|first| {
+                    first
+                        .to_uppercase()
+                        .chain(characters.flat_map(char::to_lowercase))
+                        .collect()
+                })
+                .unwrap_or_default()
+        })
+        .collect::<Vec<String>>()
+        .join("");
+
+    match ident.as_str() {
+        "" => "Empty".to_owned(),
+        "Self" => "Self_".to_owned(),
+        _ if ident.starts_with(|character: char| character.is_ascii_digit()) => {
+            format!("V{ident}")
+        }
+        _ => ident,
+    }
+}
+
+fn unique(ident: String, seen: &mut HashSet<String>) -> String {
+    let mut candidate = ident.clone();
+    let mut counter = 2;
+
+    while !seen.insert(candidate.clone()) {
+        candidate = format!("{ident}{counter}");
+        counter += 1;
+    }
+
+    candidate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn generates_struct_enums_and_filters() {
+        let database = serde_json::from_value::<Database>(json!({
+            "id": "bc1211ca-e3f1-4939-ae34-5260b16f627c",
+            "title": [],
+            "description": [],
+            "url": "https://www.notion.so/bc1211cae3f14939ae345260b16f627c",
+            "parent": { "type": "workspace", "workspace": true },
+            "created_time": "2022-03-01T19:05:00.000Z",
+            "last_edited_time": "2022-07-06T20:25:00.000Z",
+            "last_edited_by": { "id": "ee5f0f84-409a-440f-983a-a5315961c6e4" },
+            "icon": null,
+            "cover": null,
+            "archived": false,
+            "is_inline": false,
+            "properties": {
+                "Name": { "id": "title", "name": "Name", "type": "title", "title": {} },
+                "In stock": { "id": "a%3Bc", "name": "In stock", "type": "checkbox", "checkbox": {} },
+                "Food group": {
+                    "id": "b%3Bd",
+                    "name": "Food group",
+                    "type": "select",
+                    "select": {
+                        "options": [
+                            { "id": "1", "name": "🥦 Vegetable", "color": "green" },
+                            { "id": "2", "name": "Fruit", "color": "red" }
+                        ]
+                    }
+                },
+                "type": { "id": "c%3Be", "name": "type", "type": "rollup", "rollup": {} }
+            }
+        }))
+        .unwrap();
+
+        let output = generate_database_types("Grocery", &database);
+
+        assert!(output.contains("pub enum GroceryFoodGroup {"));
+        assert!(output.contains("    #[notion(name = \"🥦 Vegetable\")]\n    Vegetable,"));
+        assert!(output.contains("Self::Fruit => notion_client::Color::Red,"));
+        assert!(output.contains(
+            "    #[notion(id = \"title\", name = \"Name\", kind = \"title\")]\n    pub name: String,"
+        ));
+        assert!(output.contains("    pub food_group: Option<GroceryFoodGroup>,"));
+        assert!(output.contains("    pub in_stock: bool,"));
+        assert!(output.contains("    // \"type\" is not supported yet"));
+        assert!(output.contains(
+            "json!({ \"property\": \"a%3Bc\", \"checkbox\": { condition: value.into() } })"
+        ));
+        assert!(output.contains("pub fn food_group_equals(option: GroceryFoodGroup) -> Value {"));
+    }
+
+    #[test]
+    fn formula_filters_nest_the_result_type() {
+        let database = serde_json::from_value::<Database>(json!({
+            "id": "bc1211ca-e3f1-4939-ae34-5260b16f627c",
+            "title": [],
+            "description": [],
+            "url": "https://www.notion.so/bc1211cae3f14939ae345260b16f627c",
+            "parent": { "type": "workspace", "workspace": true },
+            "created_time": "2022-03-01T19:05:00.000Z",
+            "last_edited_time": "2022-07-06T20:25:00.000Z",
+            "last_edited_by": { "id": "ee5f0f84-409a-440f-983a-a5315961c6e4" },
+            "icon": null,
+            "cover": null,
+            "archived": false,
+            "is_inline": false,
+            "properties": {
+                "Total": {
+                    "id": "f%3Bg",
+                    "name": "Total",
+                    "type": "formula",
+                    "formula": { "expression": "prop(\"Price\") * 2", "suspected_type": "number" }
+                }
+            }
+        }))
+        .unwrap();
+
+        let output = generate_database_types("Order", &database);
+
+        assert!(
+            output.contains(
+                "    /// Filters on \"Total\", e.g. `total(\"equals\", ...)`.
+    pub fn total(condition: &str, value: impl Into<Value>) -> Value {
+        json!({ \"property\": \"f%3Bg\", \"formula\": { \"number\": { condition: value.into() } } })
+    }
+"
+            ),
+            "{output}"
+        );
+    }
+
+    #[test]
+    fn enum_names_do_not_collide() {
+        let select = |id: &str, name: &str| {
+            json!({
+                "id": id,
+                "name": name,
+                "type": "select",
+                "select": { "options": [{ "id": "1", "name": "Fruit", "color": "red" }] }
+            })
+        };
+        let database = serde_json::from_value::<Database>(json!({
+            "id": "bc1211ca-e3f1-4939-ae34-5260b16f627c",
+            "title": [],
+            "description": [],
+            "url": "https://www.notion.so/bc1211cae3f14939ae345260b16f627c",
+            "parent": { "type": "workspace", "workspace": true },
+            "created_time": "2022-03-01T19:05:00.000Z",
+            "last_edited_time": "2022-07-06T20:25:00.000Z",
+            "last_edited_by": { "id": "ee5f0f84-409a-440f-983a-a5315961c6e4" },
+            "icon": null,
+            "cover": null,
+            "archived": false,
+            "is_inline": false,
+            "properties": {
+                "Food group": select("a%3Bc", "Food group"),
+                "food-group": select("b%3Bd", "food-group"),
+                "Filter": select("c%3Be", "Filter")
+            }
+        }))
+        .unwrap();
+
+        let output = generate_database_types("Grocery", &database);
+
+        assert!(output.contains("pub enum GroceryFoodGroup {"));
+        assert!(output.contains("pub enum GroceryFoodGroup2 {"));
+        assert!(output.contains("pub enum GroceryFilter2 {"));
+        assert_eq!(
+            output.matches("pub struct GroceryFilter;").count(),
+            1,
+            "{output}"
+        );
+        assert!(output.contains("    pub filter: Option<GroceryFilter2>,"));
+    }
+
+    #[test]
+    fn sanitizes_identifiers() {
+        assert_eq!(field_ident("Due date"), "due_date");
+        assert_eq!(field_ident("type"), "r#type");
+        assert_eq!(field_ident("2nd reviewer"), "_2nd_reviewer");
+        assert_eq!(type_ident("in progress"), "InProgress");
+        assert_eq!(type_ident("camelCase"), "CamelCase");
+    }
+}
@@ -0,0 +1,402 @@
+//! Property-level differences between two versions of a page.
+
+use std::collections::BTreeMap;
+
+use crate::mapping::plain_text;
+use crate::{Date, File, Icon, Page, Property, RichText, SelectOption};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyChange {
+    /// A property only the newer page has, e.g. after it was added to the database.
+    Added {
+        name: String,
+        property: Property,
+    },
+    Removed {
+        name: String,
+        property: Property,
+    },
+    Changed {
+        /// The name in the newer page, which differs from the older one if it was renamed.
+        name: String,
+        change: ValueChange,
+    },
+    Icon {
+        before: Option<Icon>,
+        after: Option<Icon>,
+    },
+    Cover {
+        before: Option<File>,
+        after: Option<File>,
+    },
+    Archived {
+        before: bool,
+        after: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueChange {
+    /// A select or status property.
+    Select {
+        before: Option<SelectOption>,
+        after: Option<SelectOption>,
+    },
+    MultiSelect {
+        added: Vec<SelectOption>,
+        removed: Vec<SelectOption>,
+    },
+    Date {
+        before: Option<Date>,
+        after: Option<Date>,
+    },
+    /// A title or rich text property, with a word-level diff of the plain text.
+    Text {
+        before: Vec<RichText>,
+        after: Vec<RichText>,
+        diff: Vec<TextChange>,
+    },
+    /// Any other property.
+    Value { before: Property, after: Property },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextChange {
+    Equal(String),
+    Inserted(String),
+    Deleted(String),
+}
+
+impl Page {
+    /// Compares the properties of this page with those of a newer version by property id, along
+    /// with its icon, cover and archived flag.
+    pub fn diff(&self, other: &Page) -> Vec<PropertyChange> {
+        let before = by_id(self);
+        let after = by_id(other);
+
+        let mut changes = Vec::new();
+        for (id, (name, property)) in &after {
+            match before.get(id) {
+                None => changes.push(PropertyChange::Added {
+                    name: name.to_string(),
+                    property: (*property).clone(),
+                }),
+                Some((_, previous)) if previous != property => {
+                    changes.push(PropertyChange::Changed {
+                        name: name.to_string(),
+                        change: value_change(previous, property),
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+        for (id, (name, property)) in &before {
+            if !after.contains_key(id) {
+                changes.push(PropertyChange::Removed {
+                    name: name.to_string(),
+                    property: (*property).clone(),
+                });
+            }
+        }
+
+        if self.icon != other.icon {
+            changes.push(PropertyChange::Icon {
+                before: self.icon.clone(),
+                after: other.icon.clone(),
+            });
+        }
+        if self.cover != other.cover {
+            changes.push(PropertyChange::Cover {
+                before: self.cover.clone(),
+                after: other.cover.clone(),
+            });
+        }
+        let (archived, other_archived) = (
+            self.archived || self.in_trash == Some(true),
+            other.archived || other.in_trash == Some(true),
+        );
+        if archived != other_archived {
+            changes.push(PropertyChange::Archived {
+                before: archived,
+                after: other_archived,
+            });
+        }
+
+        changes
+    }
+}
+
+/// Properties by id, falling back to the name for those without one.
+fn by_id(page: &Page) -> BTreeMap<String, (&str, &Property)> {
+    page.properties
+        .iter()
+        .map(|(name, property)| {
+            (
+                property.id().unwrap_or_else(|| name.clone()),
+                (name.as_str(), property),
+            )
+        })
+        .collect()
+}
+
+fn value_change(before: &Property, after: &Property) -> ValueChange {
+    match (before, after) {
+        (Property::Select { select: before, .. }, Property::Select { select: after, .. })
+        | (Property::Status { status: before, .. }, Property::Status { status: after, .. }) => {
+            ValueChange::Select {
+                before: before.clone(),
+                after: after.clone(),
+            }
+        }
+        (
+            Property::MultiSelect {
+                multi_select: before,
+                ..
+            },
+            Property::MultiSelect {
+                multi_select: after,
+                ..
+            },
+        ) => ValueChange::MultiSelect {
+            added: after
+                .iter()
+                .filter(|option| !before.iter().any(|previous| previous.id == option.id))
+                .cloned()
+                .collect(),
+            removed: before
+                .iter()
+                .filter(|option| !after.iter().any(|next| next.id == option.id))
+                .cloned()
+                .collect(),
+        },
+        (Property::Date { date: before, .. }, Property::Date { date: after, .. }) => {
+            ValueChange::Date {
+                before: before.clone(),
+                after: after.clone(),
+            }
+        }
+        (Property::Title { title: before, .. }, Property::Title { title: after, .. })
+        | (
+            Property::RichText {
+                rich_text: before, ..
+            },
+            Property::RichText {
+                rich_text: after, ..
+            },
+        ) => ValueChange::Text {
+            diff: diff_words(&plain_text(before), &plain_text(after)),
+            before: before.clone(),
+            after: after.clone(),
+        },
+        _ => ValueChange::Value {
+            before: before.clone(),
+            after: after.clone(),
+        },
+    }
+}
+
+/// Splits text into words and the whitespace between them.
+fn words(text: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut start = 0;
+    let mut whitespace = None;
+
+    for (index, character) in text.char_indices() {
+        let is_whitespace = character.is_whitespace();
+        if whitespace.is_some_and(|whitespace| whitespace != is_whitespace) {
+            words.push(&text[start..index]);
+            start = index;
+        }
+        whitespace = Some(is_whitespace);
+    }
+    if start < text.len() {
+        words.push(&text[start..]);
+    }
+
+    words
+}
+
+/// The most cells of the longest common subsequence table, past which the changed middle of two
+/// texts is reported as replaced as a whole.
+const MAX_TABLE_CELLS: usize = 1 << 20;
+
+/// A diff along the longest common subsequence of words, with deletions before insertions.
+fn diff_words(before: &str, after: &str) -> Vec<TextChange> {
+    let (before, after) = (words(before), words(after));
+
+    let prefix = before
+        .iter()
+        .zip(&after)
+        .take_while(|(before, after)| before == after)
+        .count();
+    let suffix = before[prefix..]
+        .iter()
+        .rev()
+        .zip(after[prefix..].iter().rev())
+        .take_while(|(before, after)| before == after)
+        .count();
+    let (middle_before, middle_after) = (
+        &before[prefix..before.len() - suffix],
+        &after[prefix..after.len() - suffix],
+    );
+
+    let mut changes: Vec<TextChange> = Vec::new();
+    let mut push = |change: TextChange| match (changes.last_mut(), change) {
+        (Some(TextChange::Equal(text)), TextChange::Equal(word))
+        | (Some(TextChange::Inserted(text)), TextChange::Inserted(word))
+        | (Some(TextChange::Deleted(text)), TextChange::Deleted(word)) => text.push_str(&word),
+        (_, change) => changes.push(change),
+    };
+
+    for word in &before[..prefix] {
+        push(TextChange::Equal((*word).to_owned()));
+    }
+    if (middle_before.len() + 1).saturating_mul(middle_after.len() + 1) > MAX_TABLE_CELLS {
+        for word in middle_before {
+            push(TextChange::Deleted((*word).to_owned()));
+        }
+        for word in middle_after {
+            push(TextChange::Inserted((*word).to_owned()));
+        }
+    } else {
+        for change in diff_middle(middle_before, middle_after) {
+            push(change);
+        }
+    }
+    for word in &before[before.len() - suffix..] {
+        push(TextChange::Equal((*word).to_owned()));
+    }
+
+    changes
+}
+
+fn diff_middle(before: &[&str], after: &[&str]) -> Vec<TextChange> {
+    // lengths[i][j] is the length of the longest common subsequence of before[i..] and after[j..]
+    let mut lengths = vec![vec![0usize; after.len() + 1]; before.len() + 1];
+    for i in (0..before.len()).rev() {
+        for j in (0..after.len()).rev() {
+            lengths[i][j] = if before[i] == after[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < before.len() || j < after.len() {
+        if i < before.len() && j < after.len() && before[i] == after[j] {
+            changes.push(TextChange::Equal(before[i].to_owned()));
+            i += 1;
+            j += 1;
+        } else if i < before.len() && (j == after.len() || lengths[i + 1][j] >= lengths[i][j + 1]) {
+            changes.push(TextChange::Deleted(before[i].to_owned()));
+            i += 1;
+        } else {
+            changes.push(TextChange::Inserted(after[j].to_owned()));
+            j += 1;
+        }
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn page(properties: Value, archived: bool) -> Page {
+        serde_json::from_value(json!({
+            "id": "59833787-2cf9-4fdf-8782-e53db20768a5",
+            "created_by": { "id": "ee5f0f84-409a-440f-983a-a5315961c6e4" },
+            "url": "https://www.notion.so/59833787-2cf9-4fdf-8782-e53db20768a5",
+            "parent": { "type": "workspace", "workspace": true },
+            "created_time": "2022-03-01T19:05:00.000Z",
+            "last_edited_time": "2022-07-06T20:25:00.000Z",
+            "cover": null,
+            "icon": null,
+            "properties": properties,
+            "archived": archived
+        }))
+        .unwrap()
+    }
+
+    fn option(id: &str, name: &str) -> Value {
+        json!({ "id": id, "name": name, "color": "default" })
+    }
+
+    fn text(content: &str) -> Value {
+        json!([{
+            "type": "text",
+            "text": { "content": content, "link": null },
+            "annotations": {
+                "bold": false, "italic": false, "strikethrough": false,
+                "underline": false, "code": false, "color": "default"
+            },
+            "plain_text": content,
+            "href": null
+        }])
+    }
+
+    #[test]
+    fn reports_typed_changes() {
+        let before = page(
+            json!({
+                "Name": { "id": "title", "type": "title", "title": text("Quarterly report draft") },
+                "Tags": { "id": "t", "type": "multi_select", "multi_select": [option("a", "Finance"), option("b", "Q1")] },
+                "Stage": { "id": "s", "type": "select", "select": option("d", "Draft") },
+                "Points": { "id": "p", "type": "number", "number": 3 },
+            }),
+            false,
+        );
+        let after = page(
+            json!({
+                "Title": { "id": "title", "type": "title", "title": text("Quarterly report final") },
+                "Tags": { "id": "t", "type": "multi_select", "multi_select": [option("a", "Finance"), option("c", "Q2")] },
+                "Stage": { "id": "s", "type": "select", "select": option("d", "Draft") },
+                "Done": { "id": "x", "type": "checkbox", "checkbox": true },
+            }),
+            true,
+        );
+
+        let changes = before.diff(&after);
+
+        assert_eq!(changes.len(), 5, "{changes:#?}");
+        assert!(matches!(&changes[0], PropertyChange::Changed {
+            change: ValueChange::MultiSelect { added, removed }, ..
+        } if added[0].name == "Q2" && removed[0].name == "Q1"));
+        assert!(
+            matches!(&changes[1], PropertyChange::Changed { name, change: ValueChange::Text { diff, .. } }
+            if name == "Title" && diff == &[
+                TextChange::Equal("Quarterly report ".to_owned()),
+                TextChange::Deleted("draft".to_owned()),
+                TextChange::Inserted("final".to_owned()),
+            ])
+        );
+        assert!(matches!(&changes[2], PropertyChange::Added { name, .. } if name == "Done"));
+        assert!(matches!(&changes[3], PropertyChange::Removed { name, .. } if name == "Points"));
+        assert_eq!(
+            changes[4],
+            PropertyChange::Archived {
+                before: false,
+                after: true
+            }
+        );
+    }
+
+    #[test]
+    fn long_texts_keep_their_common_ends() {
+        let before = format!("Intro {} outro", vec!["a"; 2000].join(" "));
+        let after = format!("Intro {} outro", vec!["b"; 2000].join(" "));
+
+        let diff = diff_words(&before, &after);
+
+        assert_eq!(diff.len(), 4);
+        assert_eq!(diff[0], TextChange::Equal("Intro ".to_owned()));
+        assert!(matches!(&diff[1], TextChange::Deleted(text) if text.starts_with("a a")));
+        assert!(matches!(&diff[2], TextChange::Inserted(text) if text.starts_with("b b")));
+        assert_eq!(diff[3], TextChange::Equal(" outro".to_owned()));
+    }
+}
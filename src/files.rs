@@ -0,0 +1,233 @@
+//! Downloads of the files attached to blocks, pages and databases.
+
+use chrono::Utc;
+use futures_util::io::{AsyncWrite, AsyncWriteExt};
+use reqwest::StatusCode;
+
+use crate::{
+    BlockId, BlockOptions, Client, DatabaseId, DatabaseOptions, DateValue, Error, File, PageId,
+    PageOptions, Property, Result,
+};
+
+/// The object a file was taken from, which is fetched again for a fresh URL once the signed URL
+/// of a Notion-hosted file expired.
+pub enum FileParent {
+    /// An image, video, PDF or file block.
+    Block(BlockId),
+    /// The icon, cover or a files property of a page.
+    Page(PageId),
+    /// The icon or cover of a database.
+    Database(DatabaseId),
+}
+
+pub struct DownloadOptions<'a> {
+    /// A `File`, or the `Icon::file` of an icon.
+    pub file: &'a File,
+    pub parent: FileParent,
+}
+
+impl Client {
+    /// Streams a file to `writer` and returns the number of bytes written.
+    ///
+    /// The URLs of Notion-hosted files expire after an hour, so expired (or rejected) URLs are
+    /// replaced by the URL of the same file in a fresh copy of `options.parent`.
+    pub async fn download<W: AsyncWrite + Unpin>(
+        &self,
+        options: DownloadOptions<'_>,
+        writer: &mut W,
+    ) -> Result<u64> {
+        let mut file = options.file.clone();
+        let mut refreshed = false;
+        if is_expired(&file) {
+            file = self.refresh(&options.parent, &file).await?;
+            refreshed = true;
+        }
+
+        let mut response = loop {
+            let mut request = self.http_client.get(file.url());
+            let response = (self.request_handler)(&mut request).await?;
+
+            let expired = matches!(
+                response.status(),
+                StatusCode::FORBIDDEN | StatusCode::BAD_REQUEST
+            );
+            if expired && !refreshed && matches!(file, File::File { .. }) {
+                file = self.refresh(&options.parent, &file).await?;
+                refreshed = true;
+                continue;
+            }
+
+            match response.error_for_status_ref() {
+                Ok(_) => break response,
+                Err(error) => return Err(Error::Http(error, None)),
+            }
+        };
+
+        let mut written = 0;
+        while let Some(chunk) = response.chunk().await? {
+            writer.write_all(&chunk).await?;
+            written += chunk.len() as u64;
+        }
+        writer.flush().await?;
+
+        Ok(written)
+    }
+
+    /// Finds `file` in a fresh copy of `parent`.
+    async fn refresh(&self, parent: &FileParent, file: &File) -> Result<File> {
+        let files = match parent {
+            FileParent::Block(block_id) => {
+                let block = self
+                    .blocks
+                    .retrieve(BlockOptions {
+                        block_id: block_id.clone(),
+                    })
+                    .await?;
+
+                block.block.file().cloned().into_iter().collect()
+            }
+            FileParent::Page(page_id) => {
+                let page = self
+                    .pages
+                    .clone()
+                    .retrieve(PageOptions {
+                        page_id: page_id.clone(),
+                    })
+                    .await?;
+
+                let mut files: Vec<File> =
+                    page.icon.and_then(|icon| icon.file()).into_iter().collect();
+                files.extend(page.cover);
+                for property in page.properties.into_values() {
+                    if let Property::Files {
+                        files: property, ..
+                    } = property
+                    {
+                        files.extend(property);
+                    }
+                }
+
+                files
+            }
+            FileParent::Database(database_id) => {
+                let database = self
+                    .databases
+                    .retrieve(DatabaseOptions {
+                        database_id: database_id.clone(),
+                    })
+                    .await?;
+
+                let mut files: Vec<File> = database
+                    .icon
+                    .and_then(|icon| icon.file())
+                    .into_iter()
+                    .collect();
+                files.extend(database.cover);
+
+                files
+            }
+        };
+
+        // Only the signature in the query changes between URLs of the same file
+        files
+            .into_iter()
+            .find(|candidate| without_query(candidate.url()) == without_query(file.url()))
+            .ok_or_else(|| Error::MissingFile(file.url().to_owned()))
+    }
+}
+
+fn is_expired(file: &File) -> bool {
+    match file {
+        // Leave some time for the download to start
+        File::File { file } => match file.expiry_time {
+            DateValue::DateTime(expiry_time) => {
+                expiry_time <= Utc::now() + chrono::Duration::seconds(30)
+            }
+            DateValue::Date(_) => false,
+        },
+        File::External { .. } | File::FileUpload { .. } => false,
+    }
+}
+
+fn without_query(url: &str) -> &str {
+    url.split(['?', '#']).next().unwrap_or(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const FILE_URL: &str =
+        "https://prod-files-secure.s3.us-west-2.amazonaws.com/workspace/file/report.pdf";
+
+    fn file(signature: &str) -> serde_json::Value {
+        json!({
+            "type": "file",
+            "file": {
+                "url": format!("{FILE_URL}?X-Amz-Signature={signature}"),
+                "expiry_time": "2099-01-01T00:00:00.000Z"
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn rejected_urls_are_refreshed() {
+        let client = Client::new()
+            .api_key("secret")
+            .custom_request(|request| {
+                Box::pin(async move {
+                    let request = request.try_clone().unwrap().build()?;
+                    let url = request.url().as_str();
+                    let authorized = request.headers().contains_key("Authorization");
+
+                    let (status, body) = if url.ends_with("/v1/blocks/block") {
+                        assert!(authorized);
+                        let block = json!({
+                            "object": "block",
+                            "id": "block",
+                            "parent": { "type": "page_id", "page_id": "page" },
+                            "created_time": "2024-01-01T00:00:00.000Z",
+                            "last_edited_time": "2024-01-01T00:00:00.000Z",
+                            "created_by": { "object": "user", "id": "user" },
+                            "last_edited_by": { "object": "user", "id": "user" },
+                            "has_children": false,
+                            "archived": false,
+                            "type": "pdf",
+                            "pdf": file("fresh"),
+                        });
+                        (200, block.to_string())
+                    } else if url.ends_with("=fresh") {
+                        assert!(!authorized);
+                        (200, "%PDF".to_owned())
+                    } else {
+                        assert!(!authorized);
+                        (403, "<Error><Code>AccessDenied</Code></Error>".to_owned())
+                    };
+
+                    let mut response = http::Response::new(body);
+                    *response.status_mut() = http::StatusCode::from_u16(status).unwrap();
+
+                    Ok(reqwest::Response::from(response))
+                })
+            })
+            .build()
+            .unwrap();
+
+        let stale: File = serde_json::from_value(file("stale")).unwrap();
+        let mut bytes = Vec::new();
+        let written = client
+            .download(
+                DownloadOptions {
+                    file: &stale,
+                    parent: FileParent::Block(BlockId::new_unchecked("block")),
+                },
+                &mut bytes,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(written, 4);
+        assert_eq!(bytes, b"%PDF");
+    }
+}
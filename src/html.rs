@@ -0,0 +1,718 @@
+//! Rendering of block trees as HTML.
+//!
+//! Children are read from the `children` fields of the blocks, so they need to be fetched
+//! beforehand. All text and URLs are escaped, URLs with schemes other than `http`, `https`,
+//! `mailto` and `tel` are replaced with `#`, and colors become `notion-*` CSS classes instead of
+//! inline styles, so the output can be embedded as is.
+
+use crate::{Annotations, Block, BlockType, CodeLanguage, Color, Icon, Mention, RichText};
+
+/// Decides where mentions, child pages and links to Notion pages point to.
+pub trait LinkResolver {
+    fn page_url(&self, page_id: &str) -> String {
+        notion_url(page_id)
+    }
+
+    fn database_url(&self, database_id: &str) -> String {
+        notion_url(database_id)
+    }
+
+    /// Rewrites the URL of a link in rich text. Links to other pages of the workspace are
+    /// relative (`/<page id>`) in Notion.
+    fn link_url(&self, url: &str) -> String {
+        if url.starts_with('/') {
+            format!("https://www.notion.so{url}")
+        } else {
+            url.to_owned()
+        }
+    }
+
+    /// Renders a mention as raw HTML, which is **not** escaped. Returning `None` renders the
+    /// mention as a link or as its plain text.
+    fn mention(&self, _mention: &Mention, _plain_text: &str) -> Option<String> {
+        None
+    }
+}
+
+/// Links everything to notion.so.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NotionLinks;
+
+impl LinkResolver for NotionLinks {}
+
+#[derive(Debug, Clone, Default)]
+pub struct HtmlRenderer<R = NotionLinks> {
+    resolver: R,
+}
+
+impl HtmlRenderer {
+    pub fn new() -> HtmlRenderer {
+        HtmlRenderer::default()
+    }
+}
+
+impl<R: LinkResolver> HtmlRenderer<R> {
+    pub fn with_resolver(resolver: R) -> HtmlRenderer<R> {
+        HtmlRenderer { resolver }
+    }
+
+    /// Renders `blocks` (and their children) as HTML, one top level element per line.
+    pub fn render_blocks(&self, blocks: &[Block]) -> String {
+        let mut output = Vec::new();
+        let mut list: Option<(&str, Vec<String>)> = None;
+
+        for block in blocks {
+            let tag = match block.block {
+                BlockType::BulletedListItem { .. } => Some("ul"),
+                BlockType::NumberedListItem { .. } => Some("ol"),
+                BlockType::ToDo { .. } => Some("ul class=\"notion-to-do-list\""),
+                _ => None,
+            };
+
+            if list.as_ref().map(|(open, _)| *open) != tag {
+                if let Some((open, items)) = list.take() {
+                    output.push(close_list(open, items));
+                }
+                list = tag.map(|tag| (tag, Vec::new()));
+            }
+
+            let rendered = self.render_block(block);
+            match &mut list {
+                Some((_, items)) => items.push(rendered),
+                None if !rendered.is_empty() => output.push(rendered),
+                None => {}
+            }
+        }
+
+        if let Some((open, items)) = list {
+            output.push(close_list(open, items));
+        }
+
+        output.join("\n")
+    }
+
+    /// Renders `rich_text` as inline HTML.
+    pub fn render_rich_text(&self, rich_text: &[RichText]) -> String {
+        rich_text
+            .iter()
+            .map(|rich_text| self.render_rich_text_item(rich_text))
+            .collect()
+    }
+
+    fn render_block(&self, block: &Block) -> String {
+        match &block.block {
+            BlockType::Paragraph { paragraph } => format!(
+                "<p{}>{}</p>{}",
+                class("", &paragraph.color),
+                self.render_rich_text(&paragraph.rich_text),
+                self.indented_children(block, &paragraph.children),
+            ),
+            BlockType::Heading1 { heading } => self.heading("h1", block, heading),
+            BlockType::Heading2 { heading } => self.heading("h2", block, heading),
+            BlockType::Heading3 { heading } => self.heading("h3", block, heading),
+            BlockType::BulletedListItem {
+                bulleted_list_item: item,
+            }
+            | BlockType::NumberedListItem {
+                numbered_list_item: item,
+            } => format!(
+                "<li{}>{}{}</li>",
+                class("", &item.color),
+                self.render_rich_text(&item.rich_text),
+                self.nested_children(block, &item.children),
+            ),
+            BlockType::ToDo { to_do } => format!(
+                "<li{}><input type=\"checkbox\" disabled{}> {}{}</li>",
+                class("notion-to-do", &to_do.color),
+                if to_do.checked.unwrap_or_default() {
+                    " checked"
+                } else {
+                    ""
+                },
+                self.render_rich_text(&to_do.rich_text),
+                self.nested_children(block, &to_do.children),
+            ),
+            BlockType::Toggle { toggle } => format!(
+                "<details{}><summary>{}</summary>{}</details>",
+                class("notion-toggle", &toggle.color),
+                self.render_rich_text(&toggle.rich_text),
+                self.nested_children(block, &toggle.children),
+            ),
+            BlockType::Quote { quote } => format!(
+                "<blockquote{}>{}{}</blockquote>",
+                class("", &quote.color),
+                self.render_rich_text(&quote.rich_text),
+                self.nested_children(block, &quote.children),
+            ),
+            BlockType::Callout { callout } => {
+                let icon = match &callout.icon {
+                    Some(Icon::Emoji { emoji }) => format!(
+                        "<span class=\"notion-callout-icon\">{}</span>",
+                        escape(emoji)
+                    ),
+                    Some(Icon::File {
+                        file: crate::NotionFile { url, .. },
+                    })
+                    | Some(Icon::External {
+                        external: crate::ExternalFile { url },
+                    }) => format!(
+                        "<img class=\"notion-callout-icon\" src=\"{}\" alt=\"\">",
+                        safe_url(url)
+                    ),
+                    None => String::new(),
+                };
+
+                format!(
+                    "<div{}>{icon}<div class=\"notion-callout-content\">{}{}</div></div>",
+                    class("notion-callout", &callout.color),
+                    self.render_rich_text(&callout.rich_text),
+                    self.nested_children(block, &callout.children),
+                )
+            }
+            BlockType::Code { code } => {
+                let language = match &code.language {
+                    CodeLanguage::PlainText => String::new(),
+                    language => serde_json::to_value(language)
+                        .ok()
+                        .and_then(|value| {
+                            value.as_str().map(|language| {
+                                format!(
+                                    " class=\"language-{}\"",
+                                    escape(&language.replace(' ', "-"))
+                                )
+                            })
+                        })
+                        .unwrap_or_default(),
+                };
+                let content = escape(&crate::mapping::plain_text(&code.rich_text));
+
+                figure(
+                    "notion-code",
+                    format!("<pre><code{language}>{content}</code></pre>"),
+                    &self.render_rich_text(&code.caption),
+                )
+            }
+            BlockType::Equation { equation } => format!(
+                "<div class=\"notion-equation\">{}</div>",
+                escape(&equation.expression)
+            ),
+            BlockType::Divider => "<hr>".to_owned(),
+            BlockType::Image { image } => figure(
+                "notion-image",
+                format!("<img src=\"{}\" alt=\"\">", safe_url(image.url())),
+                "",
+            ),
+            BlockType::Video { video } => {
+                let url = safe_url(video.url());
+                let media = if matches!(video, crate::File::File { .. }) || is_video_file(video) {
+                    format!("<video src=\"{url}\" controls></video>")
+                } else {
+                    // External videos are usually pages of video platforms
+                    format!("<iframe src=\"{url}\" allowfullscreen></iframe>")
+                };
+
+                figure("notion-video", media, "")
+            }
+            BlockType::Pdf { pdf } => {
+                let url = safe_url(pdf.url());
+
+                figure(
+                    "notion-pdf",
+                    format!(
+                        "<object data=\"{url}\" type=\"application/pdf\"><a href=\"{url}\">{}</a></object>",
+                        escape(pdf.name())
+                    ),
+                    "",
+                )
+            }
+            BlockType::File { file } => format!(
+                "<a class=\"notion-file\" href=\"{}\">{}</a>",
+                safe_url(file.url()),
+                escape(file.name())
+            ),
+            BlockType::Bookmark { bookmark } => {
+                let caption = self.render_rich_text(&bookmark.caption);
+
+                card(
+                    "notion-bookmark",
+                    &bookmark.url,
+                    if caption.is_empty() {
+                        escape(&bookmark.url)
+                    } else {
+                        caption
+                    },
+                )
+            }
+            BlockType::Embed { embed } => card("notion-embed", &embed.url, escape(&embed.url)),
+            BlockType::LinkPreview { link_preview } => card(
+                "notion-link-preview",
+                &link_preview.url,
+                escape(&link_preview.url),
+            ),
+            BlockType::ChildPage { child_page } => format!(
+                "<a class=\"notion-page-link\" href=\"{}\">{}</a>",
+                safe_url(&self.resolver.page_url(&block.id)),
+                escape(&child_page.title)
+            ),
+            BlockType::ChildDatabase { child_database } => format!(
+                "<a class=\"notion-database-link\" href=\"{}\">{}</a>",
+                safe_url(&self.resolver.database_url(&block.id)),
+                escape(&child_database.title)
+            ),
+            BlockType::Table { table } => match &table.children {
+                Some(rows) => self.table(table, rows),
+                None => self.children(block, &None),
+            },
+            BlockType::ColumnList { column_list } => format!(
+                "<div class=\"notion-column-list\">{}</div>",
+                self.children(block, &column_list.children)
+            ),
+            BlockType::Column { column } => format!(
+                "<div class=\"notion-column\">{}</div>",
+                self.children(block, &column.children)
+            ),
+            BlockType::Breadcrumb
+            | BlockType::LinkToPage
+            | BlockType::SyncedBlock
+            | BlockType::TableOfContents
+            | BlockType::TableRow { .. }
+            | BlockType::Template
+            | BlockType::Unsupported => unsupported(block),
+        }
+    }
+
+    fn heading(&self, tag: &str, block: &Block, heading: &crate::Heading) -> String {
+        let title = format!(
+            "<{tag}{}>{}</{tag}>",
+            class("", &heading.color),
+            self.render_rich_text(&heading.rich_text)
+        );
+
+        if heading.is_toggleable {
+            format!(
+                "<details class=\"notion-toggle\"><summary>{title}</summary>{}</details>",
+                self.nested_children(block, &heading.children)
+            )
+        } else {
+            format!(
+                "{title}{}",
+                self.indented_children(block, &heading.children)
+            )
+        }
+    }
+
+    fn table(&self, table: &crate::Table, rows: &[Block]) -> String {
+        let width = table.table_width as usize;
+        let mut html = String::from("<table class=\"notion-table\">");
+
+        for (index, row) in rows.iter().enumerate() {
+            let BlockType::TableRow { table_row } = &row.block else {
+                continue;
+            };
+            let header_row = table.has_column_header && index == 0;
+
+            if header_row {
+                html.push_str("<thead>");
+            } else if index == 0 || (table.has_column_header && index == 1) {
+                html.push_str("<tbody>");
+            }
+
+            html.push_str("<tr>");
+            for column in 0..width {
+                let tag = if header_row || (table.has_row_header && column == 0) {
+                    "th"
+                } else {
+                    "td"
+                };
+                let cell = table_row
+                    .cells
+                    .get(column)
+                    .map(|cell| self.render_rich_text(cell))
+                    .unwrap_or_default();
+
+                html.push_str(&format!("<{tag}>{cell}</{tag}>"));
+            }
+            html.push_str("</tr>");
+
+            if header_row {
+                html.push_str("</thead>");
+            }
+        }
+
+        if rows.len() > usize::from(table.has_column_header) {
+            html.push_str("</tbody>");
+        }
+        html.push_str("</table>");
+
+        html
+    }
+
+    fn children(&self, block: &Block, children: &Option<Vec<Block>>) -> String {
+        match children {
+            Some(children) => self.render_blocks(children),
+            None if block.has_children => format!(
+                "<!-- children of block {} were not fetched -->",
+                escape(&block.id)
+            ),
+            None => String::new(),
+        }
+    }
+
+    fn nested_children(&self, block: &Block, children: &Option<Vec<Block>>) -> String {
+        let children = self.children(block, children);
+
+        if children.is_empty() {
+            children
+        } else {
+            format!("\n{children}")
+        }
+    }
+
+    fn indented_children(&self, block: &Block, children: &Option<Vec<Block>>) -> String {
+        let children = self.children(block, children);
+
+        if children.is_empty() {
+            children
+        } else {
+            format!("\n<div class=\"notion-indent\">{children}</div>")
+        }
+    }
+
+    fn render_rich_text_item(&self, rich_text: &RichText) -> String {
+        match rich_text {
+            RichText::Text {
+                text,
+                href,
+                annotations,
+                ..
+            } => {
+                let link = text
+                    .link
+                    .as_ref()
+                    .map(|link| link.url.as_str())
+                    .or(href.as_deref());
+
+                self.decorate(escape(&text.content), annotations, link)
+            }
+            RichText::Mention {
+                mention,
+                plain_text,
+                annotations,
+                ..
+            } => {
+                if let Some(html) = self.resolver.mention(mention, plain_text) {
+                    return html;
+                }
+
+                let text = escape(plain_text);
+                let inner = match mention {
+                    Mention::Page { page } => mention_link(&self.resolver.page_url(&page.id), text),
+                    Mention::Database { database } => {
+                        mention_link(&self.resolver.database_url(&database.id), text)
+                    }
+                    Mention::LinkPreview { link_preview } => mention_link(&link_preview.url, text),
+                    Mention::User { .. } => {
+                        format!("<span class=\"notion-mention notion-user\">{text}</span>")
+                    }
+                    Mention::Date { date } => format!(
+                        "<time class=\"notion-mention\" datetime=\"{}\">{text}</time>",
+                        escape(&date.start.to_string())
+                    ),
+                };
+
+                self.decorate(inner, annotations, None)
+            }
+            RichText::Equation {
+                equation,
+                annotations,
+                ..
+            } => self.decorate(
+                format!(
+                    "<span class=\"notion-equation\">{}</span>",
+                    escape(&equation.expression)
+                ),
+                annotations,
+                None,
+            ),
+        }
+    }
+
+    fn decorate(&self, mut html: String, annotations: &Annotations, link: Option<&str>) -> String {
+        html = html.replace('\n', "<br>");
+
+        if annotations.code {
+            html = format!("<code>{html}</code>");
+        }
+        if annotations.strikethrough {
+            html = format!("<s>{html}</s>");
+        }
+        if annotations.underline {
+            html = format!("<u>{html}</u>");
+        }
+        if annotations.italic {
+            html = format!("<em>{html}</em>");
+        }
+        if annotations.bold {
+            html = format!("<strong>{html}</strong>");
+        }
+        if annotations.color != Color::Default {
+            html = format!("<span{}>{html}</span>", class("", &annotations.color));
+        }
+        if let Some(url) = link {
+            html = format!(
+                "<a href=\"{}\">{html}</a>",
+                safe_url(&self.resolver.link_url(url))
+            );
+        }
+
+        html
+    }
+}
+
+/// Renders `blocks` (and their children) as HTML, linking pages to notion.so.
+pub fn blocks_to_html(blocks: &[Block]) -> String {
+    HtmlRenderer::new().render_blocks(blocks)
+}
+
+/// Renders `rich_text` as inline HTML, linking pages to notion.so.
+pub fn rich_text_to_html(rich_text: &[RichText]) -> String {
+    HtmlRenderer::new().render_rich_text(rich_text)
+}
+
+/// The CSS class of a color, e.g. `notion-blue` or `notion-blue-background`.
+pub fn color_class(color: &Color) -> Option<String> {
+    match color {
+        Color::Default => None,
+        color => serde_json::to_value(color).ok().and_then(|value| {
+            value
+                .as_str()
+                .map(|name| format!("notion-{}", name.replace('_', "-")))
+        }),
+    }
+}
+
+fn class(base: &str, color: &Color) -> String {
+    let classes = [Some(base.to_owned()), color_class(color)]
+        .into_iter()
+        .flatten()
+        .filter(|class| !class.is_empty())
+        .collect::<Vec<_>>();
+
+    if classes.is_empty() {
+        String::new()
+    } else {
+        format!(" class=\"{}\"", classes.join(" "))
+    }
+}
+
+fn close_list(open: &str, items: Vec<String>) -> String {
+    let tag = open.split(' ').next().unwrap_or(open);
+
+    format!("<{open}>\n{}\n</{tag}>", items.join("\n"))
+}
+
+fn figure(class: &str, content: String, caption: &str) -> String {
+    if caption.is_empty() {
+        format!("<figure class=\"{class}\">{content}</figure>")
+    } else {
+        format!("<figure class=\"{class}\">{content}<figcaption>{caption}</figcaption></figure>")
+    }
+}
+
+fn card(class: &str, url: &str, title: String) -> String {
+    format!(
+        "<a class=\"notion-card {class}\" href=\"{}\"><span class=\"notion-card-title\">{title}</span><span class=\"notion-card-url\">{}</span></a>",
+        safe_url(url),
+        escape(url)
+    )
+}
+
+fn mention_link(url: &str, text: String) -> String {
+    format!(
+        "<a class=\"notion-mention\" href=\"{}\">{text}</a>",
+        safe_url(url)
+    )
+}
+
+fn unsupported(block: &Block) -> String {
+    let kind = serde_json::to_value(&block.block)
+        .ok()
+        .and_then(|value| value.get("type")?.as_str().map(str::to_owned))
+        .unwrap_or_else(|| "unknown".to_owned());
+
+    format!(
+        "<!-- unsupported block: {} ({}) -->",
+        escape(&kind),
+        escape(&block.id)
+    )
+}
+
+fn is_video_file(file: &crate::File) -> bool {
+    let name = file.name().to_lowercase();
+
+    [".mp4", ".webm", ".ogg", ".mov", ".m4v"]
+        .iter()
+        .any(|extension| name.ends_with(extension))
+}
+
+fn notion_url(id: &str) -> String {
+    format!("https://www.notion.so/{}", id.replace('-', ""))
+}
+
+/// Escapes `url` for an attribute, replacing it with `#` if it could run scripts.
+fn safe_url(url: &str) -> String {
+    let url = url.trim();
+    let scheme = url
+        .split(['/', '?', '#'])
+        .next()
+        .and_then(|start| start.split_once(':'))
+        .map(|(scheme, _)| scheme.to_ascii_lowercase());
+
+    match scheme.as_deref() {
+        None | Some("http" | "https" | "mailto" | "tel") => escape(url),
+        Some(_) => "#".to_owned(),
+    }
+}
+
+/// Escapes text for use in element content and quoted attributes.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(character),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn block(id: &str, block: Value) -> Block {
+        let mut value = json!({
+            "id": id,
+            "parent": { "type": "page_id", "page_id": "59833787-2cf9-4fdf-8782-e53db20768a5" },
+            "created_time": "2022-03-01T19:05:00.000Z",
+            "last_edited_time": "2022-07-06T20:25:00.000Z",
+            "created_by": { "id": "ee5f0f84-409a-440f-983a-a5315961c6e4" },
+            "last_edited_by": { "id": "ee5f0f84-409a-440f-983a-a5315961c6e4" },
+            "has_children": false,
+            "archived": false
+        });
+        value
+            .as_object_mut()
+            .unwrap()
+            .extend(block.as_object().unwrap().clone());
+
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn text(content: &str, annotations: Value, link: Option<&str>) -> Value {
+        let mut base = json!({
+            "bold": false,
+            "italic": false,
+            "strikethrough": false,
+            "underline": false,
+            "code": false,
+            "color": "default"
+        });
+        base.as_object_mut()
+            .unwrap()
+            .extend(annotations.as_object().unwrap().clone());
+
+        json!({
+            "type": "text",
+            "text": { "content": content, "link": link.map(|url| json!({ "url": url })) },
+            "annotations": base,
+            "plain_text": content,
+            "href": link
+        })
+    }
+
+    struct AppLinks;
+
+    impl LinkResolver for AppLinks {
+        fn page_url(&self, page_id: &str) -> String {
+            format!("/pages/{page_id}")
+        }
+    }
+
+    #[test]
+    fn escapes_and_decorates_rich_text() {
+        let rich_text = serde_json::from_value::<Vec<RichText>>(json!([
+            text("<b>", json!({ "bold": true, "color": "red_background" }), None),
+            text("click", json!({}), Some("javascript:alert(1)")),
+            text("page", json!({ "italic": true }), Some("/0c4ea5a7")),
+            {
+                "type": "mention",
+                "mention": { "type": "page", "page": { "id": "a-b" } },
+                "annotations": text("", json!({}), None)["annotations"],
+                "plain_text": "Other \"page\"",
+                "href": null
+            }
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            rich_text_to_html(&rich_text),
+            "<span class=\"notion-red-background\"><strong>&lt;b&gt;</strong></span>\
+             <a href=\"#\">click</a>\
+             <a href=\"https://www.notion.so/0c4ea5a7\"><em>page</em></a>\
+             <a class=\"notion-mention\" href=\"https://www.notion.so/ab\">Other &quot;page&quot;</a>"
+        );
+    }
+
+    #[test]
+    fn renders_lists_callouts_and_pages() {
+        let blocks = vec![
+            block(
+                "a",
+                json!({
+                    "type": "to_do",
+                    "to_do": { "color": "default", "checked": true, "rich_text": [text("Done", json!({}), None)] }
+                }),
+            ),
+            block(
+                "b",
+                json!({
+                    "type": "callout",
+                    "callout": {
+                        "icon": { "type": "emoji", "emoji": "💡" },
+                        "color": "blue_background",
+                        "rich_text": [text("Tip", json!({}), None)]
+                    }
+                }),
+            ),
+            block(
+                "c",
+                json!({ "type": "child_page", "child_page": { "title": "Notes & more" } }),
+            ),
+            block(
+                "d",
+                json!({ "type": "bookmark", "bookmark": { "caption": [], "url": "https://example.com/?a=1&b=2" } }),
+            ),
+        ];
+
+        assert_eq!(
+            HtmlRenderer::with_resolver(AppLinks).render_blocks(&blocks),
+            [
+                "<ul class=\"notion-to-do-list\">",
+                "<li class=\"notion-to-do\"><input type=\"checkbox\" disabled checked> Done</li>",
+                "</ul>",
+                "<div class=\"notion-callout notion-blue-background\"><span class=\"notion-callout-icon\">💡</span><div class=\"notion-callout-content\">Tip</div></div>",
+                "<a class=\"notion-page-link\" href=\"/pages/c\">Notes &amp; more</a>",
+                "<a class=\"notion-card notion-bookmark\" href=\"https://example.com/?a=1&amp;b=2\"><span class=\"notion-card-title\">https://example.com/?a=1&amp;b=2</span><span class=\"notion-card-url\">https://example.com/?a=1&amp;b=2</span></a>",
+            ]
+            .join("\n")
+        );
+    }
+}
@@ -0,0 +1,333 @@
+//! Ids of Notion objects, parsed from UUIDs or the URLs Notion shares.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result};
+
+/// The id of any Notion object, in the hyphenated lowercase form the API returns.
+///
+/// `NotionId::parse` accepts UUIDs with or without hyphens and share URLs like
+/// `https://www.notion.so/workspace/Title-0123…`, preferring the page of a `?p=` peek link over
+/// the page or database in the path. `#` anchors link to a block, which only `BlockId::parse`
+/// reads.
+///
+/// Converting a `&str` or `String` with `into` takes it as is, without validating it (e.g. `"me"`
+/// for `Users::retrieve`), leaving it to Notion to reject. The typed ids below only convert from
+/// strings with `parse`/`try_into`, or explicitly with `new_unchecked`.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(transparent)]
+pub struct NotionId(String);
+
+impl NotionId {
+    pub fn parse(input: &str) -> Result<NotionId> {
+        parse(input, &[Part::Peek, Part::Path])
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The id without hyphens, as it appears in URLs.
+    pub fn simple(&self) -> String {
+        self.0.replace('-', "")
+    }
+
+    /// The notion.so URL of the page or database with this id.
+    pub fn url(&self) -> String {
+        format!("https://www.notion.so/{}", self.simple())
+    }
+}
+
+/// The parts of a URL an id is taken from.
+enum Part {
+    /// The block of a `#` anchor.
+    Anchor,
+    /// The page of a `?p=` peek link, opened on top of a database.
+    Peek,
+    /// The page or database the URL is for.
+    Path,
+}
+
+/// Takes the id from the first of `parts` that contains one.
+fn parse(input: &str, parts: &[Part]) -> Result<NotionId> {
+    let input = input.trim();
+    let (rest, anchor) = split_off(input, '#');
+    let (rest, query) = split_off(rest, '?');
+
+    let peek = query.and_then(|query| {
+        query
+            .split('&')
+            .find_map(|parameter| parameter.strip_prefix("p="))
+    });
+    let path = rest
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or(rest);
+
+    parts
+        .iter()
+        .filter_map(|part| match part {
+            Part::Anchor => anchor,
+            Part::Peek => peek,
+            Part::Path => Some(path),
+        })
+        .find_map(find_id)
+        .map(NotionId)
+        .ok_or_else(|| Error::InvalidId(input.to_owned()))
+}
+
+fn split_off(input: &str, separator: char) -> (&str, Option<&str>) {
+    match input.split_once(separator) {
+        Some((rest, suffix)) => (rest, Some(suffix)),
+        None => (input, None),
+    }
+}
+
+/// Finds the id at the end of a URL segment, e.g. `Title-0123…` or a (hyphenated) UUID.
+fn find_id(segment: &str) -> Option<String> {
+    if let Some(id) = hyphenate(&segment.replace('-', "")) {
+        if segment.len() == 32 || segment.len() == 36 {
+            return Some(id);
+        }
+    }
+
+    let tail = segment.get(segment.len().checked_sub(32)?..)?;
+    let slug = &segment[..segment.len() - 32];
+    if slug.is_empty() || slug.ends_with('-') {
+        return hyphenate(tail);
+    }
+
+    None
+}
+
+fn hyphenate(hex: &str) -> Option<String> {
+    if hex.len() != 32 || !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let hex = hex.to_ascii_lowercase();
+    Some(format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    ))
+}
+
+impl FromStr for NotionId {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<NotionId> {
+        NotionId::parse(input)
+    }
+}
+
+impl fmt::Display for NotionId {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for NotionId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for NotionId {
+    fn from(input: &str) -> Self {
+        NotionId(input.to_owned())
+    }
+}
+
+impl From<&String> for NotionId {
+    fn from(input: &String) -> Self {
+        NotionId(input.clone())
+    }
+}
+
+impl From<String> for NotionId {
+    fn from(input: String) -> Self {
+        NotionId(input)
+    }
+}
+
+macro_rules! typed_id {
+    ($(#[$meta:meta])* $name:ident, $($part:ident),+) => {
+        $(#[$meta])*
+        #[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        #[serde(transparent)]
+        pub struct $name(NotionId);
+
+        impl $name {
+            pub fn parse(input: &str) -> Result<$name> {
+                parse(input, &[$(Part::$part),+]).map($name)
+            }
+
+            /// Takes `id` as is, e.g. an id the API returned, leaving it to Notion to reject it.
+            pub fn new_unchecked(id: impl Into<String>) -> $name {
+                $name(NotionId(id.into()))
+            }
+
+            pub fn as_str(&self) -> &str {
+                self.0.as_str()
+            }
+
+            pub fn id(&self) -> &NotionId {
+                &self.0
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = Error;
+
+            fn from_str(input: &str) -> Result<$name> {
+                $name::parse(input)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                self.0.fmt(formatter)
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                self.0.as_str()
+            }
+        }
+
+        impl From<NotionId> for $name {
+            fn from(id: NotionId) -> Self {
+                $name(id)
+            }
+        }
+
+        impl From<$name> for NotionId {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+
+        impl TryFrom<&str> for $name {
+            type Error = Error;
+
+            fn try_from(input: &str) -> Result<$name> {
+                $name::parse(input)
+            }
+        }
+
+        impl TryFrom<&String> for $name {
+            type Error = Error;
+
+            fn try_from(input: &String) -> Result<$name> {
+                $name::parse(input)
+            }
+        }
+
+        impl TryFrom<String> for $name {
+            type Error = Error;
+
+            fn try_from(input: String) -> Result<$name> {
+                $name::parse(&input)
+            }
+        }
+    };
+}
+
+typed_id!(
+    /// The id of a page, parsed from a peek link or the path of a URL.
+    PageId,
+    Peek,
+    Path
+);
+typed_id!(
+    /// The id of a block, parsed from the `#` anchor of a URL if it has one. Pages are blocks too,
+    /// so a `PageId` converts into one.
+    BlockId,
+    Anchor,
+    Peek,
+    Path
+);
+typed_id!(
+    /// The id of a database, parsed from the path of a URL.
+    DatabaseId,
+    Path
+);
+
+impl From<PageId> for BlockId {
+    fn from(id: PageId) -> Self {
+        BlockId(id.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "0d253ab0-f751-443a-afb9-bcec14012897";
+
+    #[test]
+    fn parses_uuids_and_urls() {
+        for input in [
+            "0d253ab0f751443aafb9bcec14012897",
+            "0D253AB0-F751-443A-AFB9-BCEC14012897",
+            "https://www.notion.so/0d253ab0f751443aafb9bcec14012897",
+            "https://www.notion.so/workspace/Meeting-Notes-0d253ab0f751443aafb9bcec14012897?pvs=4",
+            "https://workspace.notion.site/Notes-0d253ab0f751443aafb9bcec14012897/",
+            "https://www.notion.so/workspace/2e0b0b3f8a6c4d5e9f7a1b2c3d4e5f60?v=5a1b2c3d4e5f60718293a4b5c6d7e8f9&p=0d253ab0f751443aafb9bcec14012897&pm=s",
+            "https://www.notion.so/Page-0d253ab0f751443aafb9bcec14012897#2e0b0b3f8a6c4d5e9f7a1b2c3d4e5f60",
+        ] {
+            assert_eq!(NotionId::parse(input).unwrap().as_str(), ID, "{input}");
+        }
+    }
+
+    #[test]
+    fn typed_ids_read_their_part_of_urls() {
+        let anchor =
+            "https://www.notion.so/Page-0d253ab0f751443aafb9bcec14012897#2e0b0b3f8a6c4d5e9f7a1b2c3d4e5f60";
+        assert_eq!(PageId::parse(anchor).unwrap().as_str(), ID);
+        assert_eq!(
+            BlockId::parse(anchor).unwrap().as_str(),
+            "2e0b0b3f-8a6c-4d5e-9f7a-1b2c3d4e5f60"
+        );
+
+        let peek = "https://www.notion.so/2e0b0b3f8a6c4d5e9f7a1b2c3d4e5f60?v=5a1b2c3d4e5f60718293a4b5c6d7e8f9&p=0d253ab0f751443aafb9bcec14012897";
+        assert_eq!(PageId::parse(peek).unwrap().as_str(), ID);
+        assert_eq!(
+            DatabaseId::parse(peek).unwrap().as_str(),
+            "2e0b0b3f-8a6c-4d5e-9f7a-1b2c3d4e5f60"
+        );
+    }
+
+    #[test]
+    fn rejects_input_without_an_id() {
+        for input in [
+            "me",
+            "0d253ab0f751443aafb9bcec1401289",
+            "https://www.notion.so/workspace/Notes0d253ab0f751443aafb9bcec14012897",
+            "0d253ab0f751443aafb9bcec1401289z",
+        ] {
+            assert!(matches!(NotionId::parse(input), Err(Error::InvalidId(_))));
+        }
+
+        assert_eq!(NotionId::from("me").as_str(), "me");
+        assert_eq!(PageId::new_unchecked("me").as_str(), "me");
+        // Typed ids parse what they are converted from
+        assert!(matches!(PageId::try_from("me"), Err(Error::InvalidId(_))));
+        assert_eq!(
+            PageId::try_from("0d253ab0f751443aafb9bcec14012897")
+                .unwrap()
+                .as_str(),
+            ID
+        );
+        assert_eq!(BlockId::from(PageId::parse(ID).unwrap()).as_str(), ID);
+    }
+}
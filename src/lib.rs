@@ -1,5 +1,8 @@
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::thread::LocalKey;
+use std::time::Duration;
 
 use chrono::{DateTime, NaiveTime, Utc};
 use lazy_static::lazy_static;
@@ -12,15 +15,47 @@ use serde_json::json;
 use serde_json::Value;
 
 use futures_core::future::BoxFuture;
+use futures_util::StreamExt;
+
+#[cfg(feature = "backup")]
+pub mod backup;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod codegen;
+pub mod diff;
+#[cfg(feature = "request")]
+pub mod files;
+#[cfg(feature = "convert_from_notion")]
+pub mod html;
+pub mod id;
+pub mod mapping;
+#[cfg(any(feature = "convert_from_notion", feature = "convert_to_notion"))]
+pub mod markdown;
+#[cfg(feature = "request")]
+pub mod oauth;
+pub mod patch;
+#[cfg(feature = "pool")]
+pub mod pool;
+#[cfg(feature = "sync")]
+pub mod sync;
+#[cfg(feature = "watch")]
+pub mod watch;
+#[cfg(feature = "webhook")]
+pub mod webhook;
+
+pub use id::{BlockId, DatabaseId, NotionId, PageId};
+
+#[cfg(feature = "derive")]
+pub use notion_client_derive::{NotionPage, NotionSelect};
+
+// Lets the derive macros refer to `::notion_client` from within this crate too
+extern crate self as notion_client;
 
 lazy_static! {
     static ref ISO_8601_DATE: Regex =
         Regex::new(r"^\d{4}-\d{2}-\d{2}$").expect("ISO 8601 date regex to be parseable");
 }
 
-#[cfg(feature = "request")]
-const NOTION_VERSION: &str = "2022-06-28";
-
 pub type Result<T> = std::result::Result<T, Error>;
 pub type Callback = dyn Fn(
         &mut reqwest::RequestBuilder,
@@ -29,6 +64,28 @@ pub type Callback = dyn Fn(
     + Send
     + Sync;
 
+/// A `Callback` that can fail with errors of this crate, e.g. when no token could be provided.
+type RequestHandler = dyn Fn(&mut reqwest::RequestBuilder) -> BoxFuture<'_, Result<reqwest::Response>>
+    + 'static
+    + Send
+    + Sync;
+
+/// Supplies the bearer token of every request, e.g. to pick the OAuth access token of the
+/// workspace a request is made for, or to refresh it before it expires.
+///
+/// An error fails the request it was asked for.
+#[async_trait::async_trait]
+pub trait TokenProvider: Send + Sync {
+    async fn token(&self) -> Result<String>;
+}
+
+#[async_trait::async_trait]
+impl TokenProvider for String {
+    async fn token(&self) -> Result<String> {
+        Ok(self.clone())
+    }
+}
+
 #[derive(Debug)]
 pub enum Error {
     Http(reqwest::Error, Option<Value>),
@@ -36,6 +93,35 @@ pub enum Error {
     Header(reqwest::header::InvalidHeaderValue),
     ChronoParse(chrono::ParseError),
     UnexpectedType,
+    MissingProperty(String),
+    UnexpectedPropertyType(String),
+    /// A number property read into an integer that isn't a whole number or doesn't fit.
+    NumberOutOfRange {
+        property: String,
+        number: f64,
+    },
+    /// A select, status or multi select property with an option the type has no variant for.
+    UnknownOption {
+        property: String,
+        option: String,
+    },
+    /// `ClientBuilder::build` was called without an `api_key` or `token_provider`.
+    MissingApiKey,
+    Io(std::io::Error),
+    /// The input of `NotionId::parse` contains no id.
+    InvalidId(String),
+    /// The file to download is no longer part of the object it was taken from.
+    MissingFile(String),
+    /// A webhook event without a valid `X-Notion-Signature`.
+    InvalidSignature,
+    /// The content type of a file upload is not a valid MIME type.
+    InvalidContentType(String),
+    /// The database has no data source to query.
+    MissingDataSource(String),
+    /// A URL to send users to that couldn't be built.
+    InvalidUrl(String),
+    #[cfg(feature = "sync")]
+    Sqlite(rusqlite::Error),
 }
 
 impl std::fmt::Display for Error {
@@ -62,18 +148,140 @@ impl From<serde_json::Error> for Error {
     }
 }
 
+#[cfg(feature = "sync")]
+impl From<rusqlite::Error> for Error {
+    fn from(error: rusqlite::Error) -> Self {
+        Error::Sqlite(error)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
 impl From<chrono::ParseError> for Error {
     fn from(error: chrono::ParseError) -> Self {
         Error::ChronoParse(error)
     }
 }
 
+/// How much of Notion's homework we're willing to do while deserializing properties.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DeserializationMode {
+    /// Patch up malformed property values and fall back to `Unsupported`, recording a
+    /// `PropertyWarning` for every change on the resulting `Page`/`Database`.
+    #[default]
+    Lenient,
+    /// Fail with the path of the first property value that would have needed patching.
+    Strict,
+}
+
+/// The `Notion-Version` of requests, which also decides which version specific fields are read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ApiVersion {
+    #[default]
+    V2022_06_28,
+    /// Databases are containers of data sources, and `archived` became `in_trash`.
+    V2025_09_03,
+}
+
+impl ApiVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiVersion::V2022_06_28 => "2022-06-28",
+            ApiVersion::V2025_09_03 => "2025-09-03",
+        }
+    }
+}
+
+impl std::fmt::Display for ApiVersion {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str(self.as_str())
+    }
+}
+
+thread_local! {
+    static DESERIALIZATION_MODE: Cell<DeserializationMode> = const { Cell::new(DeserializationMode::Lenient) };
+    static PROPERTY_WARNINGS: RefCell<Vec<PropertyWarning>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Runs `callback` with every `Page`/`Database` deserialized inside of it using `mode`.
+///
+/// This is what the `Client` does for each response, and is useful when parsing recorded JSON.
+pub fn with_deserialization_mode<T>(mode: DeserializationMode, callback: impl FnOnce() -> T) -> T {
+    let _restore = Restore::set(&DESERIALIZATION_MODE, mode);
+
+    callback()
+}
+
+/// Puts back the previous value of a thread-local setting when dropped, also while unwinding.
+struct Restore<T: Copy + 'static> {
+    setting: &'static LocalKey<Cell<T>>,
+    previous: T,
+}
+
+impl<T: Copy + 'static> Restore<T> {
+    fn set(setting: &'static LocalKey<Cell<T>>, value: T) -> Self {
+        Restore {
+            setting,
+            previous: setting.with(|current| current.replace(value)),
+        }
+    }
+}
+
+impl<T: Copy + 'static> Drop for Restore<T> {
+    fn drop(&mut self) {
+        self.setting.with(|current| current.set(self.previous));
+    }
+}
+
+fn deserialization_mode() -> DeserializationMode {
+    DESERIALIZATION_MODE.with(|current| current.get())
+}
+
+fn take_property_warnings() -> Vec<PropertyWarning> {
+    PROPERTY_WARNINGS.with(|buffer| buffer.take())
+}
+
+/// Implements `Deserialize` and `Serialize` of objects with `warnings` around their derived
+/// `#[serde(remote = "Self")]` implementations, so that each object gets exactly the warnings
+/// its properties left behind, even if an object before it failed halfway.
+macro_rules! with_property_warnings {
+    ($($object:ty),*) => {$(
+        impl<'de> Deserialize<'de> for $object {
+            fn deserialize<D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> std::result::Result<Self, D::Error> {
+                take_property_warnings();
+                let object = <$object>::deserialize(deserializer);
+                let warnings = take_property_warnings();
+
+                object.map(|object| Self { warnings, ..object })
+            }
+        }
+
+        impl Serialize for $object {
+            fn serialize<S: serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> std::result::Result<S::Ok, S::Error> {
+                <$object>::serialize(self, serializer)
+            }
+        }
+    )*};
+}
+
+with_property_warnings!(Page, Database, DataSource);
+
 async fn try_to_parse_response<T: std::fmt::Debug + for<'de> serde::Deserialize<'de>>(
     response: reqwest::Response,
+    mode: DeserializationMode,
 ) -> Result<T> {
     let text = response.text().await?;
 
-    match serde_json::from_str::<T>(&text) {
+    match with_deserialization_mode(mode, || serde_json::from_str::<T>(&text)) {
         Ok(value) => Ok(value),
         Err(error) => match serde_json::from_str::<Value>(&text) {
             Ok(body) => Err(Error::Deserialization(error, Some(body))),
@@ -82,24 +290,77 @@ async fn try_to_parse_response<T: std::fmt::Debug + for<'de> serde::Deserialize<
     }
 }
 
+/// Settings of the underlying `reqwest::Client`.
+#[derive(Default)]
+struct HttpOptions {
+    connect_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    proxy: Option<reqwest::Proxy>,
+    root_certificates: Vec<reqwest::Certificate>,
+    user_agent: Option<String>,
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<Duration>,
+}
+
 #[cfg(feature = "request")]
-fn get_http_client(notion_api_key: &str) -> reqwest::Client {
+fn get_http_client(version: ApiVersion, options: HttpOptions) -> Result<reqwest::Client> {
     let mut headers = HeaderMap::new();
-    headers.insert(
-        "Authorization",
-        HeaderValue::from_str(&format!("Bearer {notion_api_key}"))
-            .expect("bearer token to be parsed into a header"),
-    );
-    headers.insert(
-        "Notion-Version",
-        HeaderValue::from_str(NOTION_VERSION).expect("notion version to be parsed into a header"),
-    );
+    headers.insert("Notion-Version", HeaderValue::from_static(version.as_str()));
     headers.insert("Content-Type", HeaderValue::from_static("application/json"));
+    if let Some(user_agent) = options.user_agent {
+        headers.insert("User-Agent", HeaderValue::from_str(&user_agent)?);
+    }
+
+    let mut builder = reqwest::ClientBuilder::new().default_headers(headers);
+    if let Some(timeout) = options.connect_timeout {
+        builder = builder.connect_timeout(timeout);
+    }
+    if let Some(timeout) = options.timeout {
+        builder = builder.timeout(timeout);
+    }
+    if let Some(proxy) = options.proxy {
+        builder = builder.proxy(proxy);
+    }
+    for certificate in options.root_certificates {
+        builder = builder.add_root_certificate(certificate);
+    }
+    if let Some(max) = options.pool_max_idle_per_host {
+        builder = builder.pool_max_idle_per_host(max);
+    }
+    if let Some(timeout) = options.pool_idle_timeout {
+        builder = builder.pool_idle_timeout(timeout);
+    }
+
+    Ok(builder.build()?)
+}
 
-    reqwest::ClientBuilder::new()
-        .default_headers(headers)
-        .build()
-        .expect("to build a valid client out of notion_api_key")
+/// Wraps `request_handler` so that every request to the API is authorized with a fresh token of
+/// `provider`. Other requests, like downloads of Notion-hosted files, go out without it.
+#[cfg(feature = "request")]
+fn with_token_provider(
+    provider: Arc<dyn TokenProvider>,
+    request_handler: Arc<Callback>,
+) -> Arc<RequestHandler> {
+    Arc::new(move |request_builder: &mut reqwest::RequestBuilder| {
+        let provider = provider.clone();
+        let request_handler = request_handler.clone();
+
+        Box::pin(async move {
+            let request = request_builder
+                .try_clone()
+                .expect("non-stream body request clone to succeed");
+            if request
+                .try_clone()
+                .and_then(|request| request.build().ok())
+                .is_some_and(|request| request.url().host_str() == Some("api.notion.com"))
+            {
+                let token = provider.token().await?;
+                *request_builder = request.bearer_auth(token);
+            }
+
+            Ok(request_handler(request_builder).await?)
+        })
+    })
 }
 
 #[derive(Serialize)]
@@ -116,10 +377,104 @@ pub struct SearchOptions<'a> {
     pub page_size: Option<u32>,
 }
 
+/// The options of `Client::search_all`.
+#[derive(Default)]
+pub struct SearchAllOptions<'a> {
+    /// Matched against page and database titles, `None` for everything shared with the
+    /// integration.
+    pub query: Option<&'a str>,
+    pub filter: Option<SearchFilter>,
+    pub sort: Option<SearchSort>,
+}
+
+/// Limits a search to one kind of object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchFilter {
+    Pages,
+    Databases,
+}
+
+impl SearchFilter {
+    /// The filter as `version` expects it, which calls databases data sources since
+    /// `ApiVersion::V2025_09_03`.
+    pub fn to_json(self, version: ApiVersion) -> Value {
+        let value = match (self, version) {
+            (SearchFilter::Pages, _) => "page",
+            (SearchFilter::Databases, ApiVersion::V2022_06_28) => "database",
+            (SearchFilter::Databases, ApiVersion::V2025_09_03) => "data_source",
+        };
+
+        json!({ "property": "object", "value": value })
+    }
+}
+
+/// Orders search results by when they were last edited, the only order Notion supports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchSort {
+    LastEditedAscending,
+    LastEditedDescending,
+}
+
+impl Serialize for SearchSort {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        let direction = match self {
+            SearchSort::LastEditedAscending => "ascending",
+            SearchSort::LastEditedDescending => "descending",
+        };
+
+        json!({ "timestamp": "last_edited_time", "direction": direction }).serialize(serializer)
+    }
+}
+
+/// A page, database or data source found by `Client::search_all`.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "object", rename_all = "snake_case")]
+pub enum SearchResult {
+    Page(Page),
+    Database(Database),
+    /// Returned instead of databases since `ApiVersion::V2025_09_03`.
+    DataSource(DataSource),
+    /// An object of a kind this crate does not know yet.
+    #[serde(untagged)]
+    Unknown(Value),
+}
+
+impl SearchResult {
+    pub fn id(&self) -> &str {
+        match self {
+            SearchResult::Page(page) => &page.id,
+            SearchResult::Database(database) => &database.id,
+            SearchResult::DataSource(data_source) => &data_source.id,
+            SearchResult::Unknown(value) => value["id"].as_str().unwrap_or_default(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for SearchResult {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+
+        match value["object"].as_str() {
+            Some("page") => serde_json::from_value(value).map(SearchResult::Page),
+            Some("database") => serde_json::from_value(value).map(SearchResult::Database),
+            Some("data_source") => serde_json::from_value(value).map(SearchResult::DataSource),
+            _ => Ok(SearchResult::Unknown(value)),
+        }
+        .map_err(D::Error::custom)
+    }
+}
+
 #[derive(Default)]
 pub struct ClientBuilder {
     api_key: Option<String>,
+    token_provider: Option<Arc<dyn TokenProvider>>,
     custom_request: Option<Arc<Callback>>,
+    deserialization_mode: DeserializationMode,
+    api_version: ApiVersion,
+    http: HttpOptions,
 }
 
 impl ClientBuilder {
@@ -129,6 +484,73 @@ impl ClientBuilder {
         self
     }
 
+    /// Asks `provider` for the token of each request instead of using a fixed `api_key`.
+    pub fn token_provider(mut self, provider: impl TokenProvider + 'static) -> Self {
+        self.token_provider = Some(Arc::new(provider));
+
+        self
+    }
+
+    pub fn deserialization_mode(mut self, mode: DeserializationMode) -> Self {
+        self.deserialization_mode = mode;
+
+        self
+    }
+
+    pub fn notion_version(mut self, version: ApiVersion) -> Self {
+        self.api_version = version;
+
+        self
+    }
+
+    /// Timeout for establishing connections.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.http.connect_timeout = Some(timeout);
+
+        self
+    }
+
+    /// Timeout for whole requests, from connecting until the response body has been read.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.http.timeout = Some(timeout);
+
+        self
+    }
+
+    /// Sends requests through `proxy` instead of the proxy of the environment (if any).
+    pub fn proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.http.proxy = Some(proxy);
+
+        self
+    }
+
+    /// Trusts `certificate` in addition to the system's root certificates.
+    pub fn add_root_certificate(mut self, certificate: reqwest::Certificate) -> Self {
+        self.http.root_certificates.push(certificate);
+
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.http.user_agent = Some(user_agent.to_owned());
+
+        self
+    }
+
+    /// Maximum number of idle connections kept open.
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.http.pool_max_idle_per_host = Some(max);
+
+        self
+    }
+
+    /// How long idle connections are kept open.
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.http.pool_idle_timeout = Some(timeout);
+
+        self
+    }
+
     pub fn custom_request<F>(mut self, callback: F) -> Self
     where
         for<'c> F: Fn(
@@ -144,61 +566,119 @@ impl ClientBuilder {
     }
 
     #[cfg(feature = "request")]
-    pub fn build(self) -> Client {
-        let notion_api_key = self.api_key.expect("api_key to be set");
+    pub fn build(self) -> Result<Client> {
+        let request_handler = self.custom_request.unwrap_or_else(default_request_handler);
+
+        let provider: Arc<dyn TokenProvider> = match (self.token_provider, self.api_key) {
+            (Some(provider), _) => provider,
+            (None, Some(notion_api_key)) => {
+                HeaderValue::from_str(&format!("Bearer {notion_api_key}"))?;
+                Arc::new(notion_api_key)
+            }
+            (None, None) => return Err(Error::MissingApiKey),
+        };
+        let http_client = get_http_client(self.api_version, self.http)?;
+
+        Ok(Client::from_parts(
+            Arc::from(http_client),
+            with_token_provider(provider, request_handler),
+            self.deserialization_mode,
+            self.api_version,
+        ))
+    }
 
-        let request_handler = self.custom_request.unwrap_or(Arc::new(
-            |request_builder: &mut reqwest::RequestBuilder| {
-                Box::pin(async move {
-                    let request = request_builder
-                        .try_clone()
-                        .expect("non-stream body request clone to succeed");
+    /// Builds a `blocking::Client`, which owns a runtime of its own.
+    #[cfg(feature = "blocking")]
+    pub fn build_blocking(self) -> Result<blocking::Client> {
+        blocking::Client::from_async(self.build()?)
+    }
+}
 
-                    request.send().await
-                })
-            },
-        ));
+#[cfg(feature = "request")]
+fn default_request_handler() -> Arc<Callback> {
+    Arc::new(|request_builder: &mut reqwest::RequestBuilder| {
+        Box::pin(async move {
+            let request = request_builder
+                .try_clone()
+                .expect("non-stream body request clone to succeed");
+
+            request.send().await
+        })
+    })
+}
+
+#[derive(Clone)]
+pub struct Client {
+    http_client: Arc<reqwest::Client>,
+    request_handler: Arc<RequestHandler>,
+    deserialization_mode: DeserializationMode,
+    api_version: ApiVersion,
+
+    pub pages: Pages,
+    pub blocks: Blocks,
+    pub databases: Databases,
+    pub users: Users,
+    pub comments: Comments,
+    pub file_uploads: FileUploads,
+}
+
+impl Client {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new() -> ClientBuilder {
+        ClientBuilder::default()
+    }
 
-        let http_client = Arc::from(get_http_client(&notion_api_key));
+    #[cfg(feature = "request")]
+    fn from_parts(
+        http_client: Arc<reqwest::Client>,
+        request_handler: Arc<RequestHandler>,
+        deserialization_mode: DeserializationMode,
+        api_version: ApiVersion,
+    ) -> Client {
+        let data_sources = Arc::new(Mutex::default());
 
         Client {
             http_client: http_client.clone(),
             request_handler: request_handler.clone(),
+            deserialization_mode,
+            api_version,
 
             pages: Pages {
                 http_client: http_client.clone(),
                 request_handler: request_handler.clone(),
+                deserialization_mode,
             },
             blocks: Blocks {
                 http_client: http_client.clone(),
                 request_handler: request_handler.clone(),
+                deserialization_mode,
+                api_version,
+                data_sources: data_sources.clone(),
             },
             databases: Databases {
                 http_client: http_client.clone(),
                 request_handler: request_handler.clone(),
+                deserialization_mode,
+                api_version,
+                data_sources,
             },
             users: Users {
                 http_client: http_client.clone(),
                 request_handler: request_handler.clone(),
+                deserialization_mode,
+            },
+            comments: Comments {
+                http_client: http_client.clone(),
+                request_handler: request_handler.clone(),
+                deserialization_mode,
+            },
+            file_uploads: FileUploads {
+                http_client: http_client.clone(),
+                request_handler: request_handler.clone(),
+                deserialization_mode,
             },
         }
     }
-}
-
-pub struct Client {
-    http_client: Arc<reqwest::Client>,
-    request_handler: Arc<Callback>,
-
-    pub pages: Pages,
-    pub blocks: Blocks,
-    pub databases: Databases,
-    pub users: Users,
-}
-
-impl<'a> Client {
-    pub fn new() -> ClientBuilder {
-        ClientBuilder::default()
-    }
 
     pub async fn search<'b, T: std::fmt::Debug + for<'de> serde::Deserialize<'de>>(
         self,
@@ -212,27 +692,83 @@ impl<'a> Client {
         let response = (self.request_handler)(&mut request).await?;
 
         match response.error_for_status_ref() {
-            Ok(_) => Ok(response.json().await?),
+            Ok(_) => try_to_parse_response(response, self.deserialization_mode).await,
             Err(error) => {
                 let body = response.json::<Value>().await?;
                 Err(Error::Http(error, Some(body)))
             }
         }
     }
+
+    /// Searches the pages and databases shared with the integration, fetching every page of
+    /// results.
+    pub async fn search_all<'b>(&self, options: SearchAllOptions<'b>) -> Result<Vec<SearchResult>> {
+        let mut results = Vec::new();
+        let mut cursor = None;
+
+        loop {
+            let mut body = json!({ "page_size": 100 });
+            if let Some(query) = options.query {
+                body["query"] = json!(query);
+            }
+            if let Some(filter) = options.filter {
+                body["filter"] = filter.to_json(self.api_version);
+            }
+            if let Some(sort) = options.sort {
+                body["sort"] = json!(sort);
+            }
+            if let Some(cursor) = &cursor {
+                body["start_cursor"] = json!(cursor);
+            }
+
+            let mut request = self
+                .http_client
+                .post("https://api.notion.com/v1/search")
+                .json(&body);
+
+            let response = (self.request_handler)(&mut request).await?;
+
+            let page = match response.error_for_status_ref() {
+                Ok(_) => {
+                    try_to_parse_response::<QueryResponse<SearchResult>>(
+                        response,
+                        self.deserialization_mode,
+                    )
+                    .await?
+                }
+                Err(error) => {
+                    let body = response.json::<Value>().await?;
+                    return Err(Error::Http(error, Some(body)));
+                }
+            };
+
+            results.extend(page.results);
+            match page.next_cursor {
+                Some(next_cursor) if page.has_more => cursor = Some(next_cursor),
+                _ => return Ok(results),
+            }
+        }
+    }
 }
 
-pub struct PageOptions<'a> {
-    pub page_id: &'a str,
+pub struct PageOptions {
+    pub page_id: PageId,
+}
+
+pub struct PagePropertyOptions<'a> {
+    pub page_id: PageId,
+    pub property_id: &'a str,
 }
 
 #[derive(Clone)]
 pub struct Pages {
     http_client: Arc<reqwest::Client>,
-    request_handler: Arc<Callback>,
+    request_handler: Arc<RequestHandler>,
+    deserialization_mode: DeserializationMode,
 }
 
 impl Pages {
-    pub async fn retrieve<'a>(self, options: PageOptions<'a>) -> Result<Page> {
+    pub async fn retrieve(self, options: PageOptions) -> Result<Page> {
         let url = format!(
             "https://api.notion.com/v1/pages/{page_id}",
             page_id = options.page_id
@@ -243,44 +779,416 @@ impl Pages {
         let response = (self.request_handler)(&mut request).await?;
 
         match response.error_for_status_ref() {
-            Ok(_) => Ok(response.json().await?),
+            Ok(_) => try_to_parse_response(response, self.deserialization_mode).await,
             Err(error) => {
                 let body = response.json::<Value>().await?;
                 Err(Error::Http(error, Some(body)))
             }
         }
     }
-}
 
-#[derive(Clone)]
-pub struct Blocks {
-    http_client: Arc<reqwest::Client>,
-    request_handler: Arc<Callback>,
-}
+    /// Retrieves a single property of a page. Unlike the properties of `retrieve`, title,
+    /// rich text, relation and people values aren't truncated to 25 items.
+    pub async fn property<'a>(&self, options: PagePropertyOptions<'a>) -> Result<PageProperty> {
+        let url = format!(
+            "https://api.notion.com/v1/pages/{page_id}/properties/{property_id}",
+            page_id = options.page_id,
+            property_id = options.property_id
+        );
 
-impl Blocks {
-    pub fn children(&self) -> BlockChildren {
-        BlockChildren {
-            http_client: self.http_client.clone(),
-            request_handler: self.request_handler.clone(),
-        }
+        let mut items = Vec::new();
+        let mut cursor = None;
+
+        let property = loop {
+            let mut request = self.http_client.get(&url);
+            if let Some(cursor) = &cursor {
+                request = request.query(&[("start_cursor", cursor)]);
+            }
+
+            let response = (self.request_handler)(&mut request).await?;
+
+            let body = match response.error_for_status_ref() {
+                Ok(_) => response.json::<Value>().await?,
+                Err(error) => {
+                    let body = response.json::<Value>().await?;
+                    return Err(Error::Http(error, Some(body)));
+                }
+            };
+
+            // Properties that aren't paginated come back as a single property item
+            if body["object"] != "list" {
+                break body;
+            }
+
+            let page = serde_json::from_value::<PropertyItemList>(body)?;
+            items.extend(page.results);
+
+            match page.next_cursor {
+                Some(next_cursor) if page.has_more => cursor = Some(next_cursor),
+                _ => break merge_property_items(page.property_item, items),
+            }
+        };
+
+        let (properties, warnings) = with_deserialization_mode(self.deserialization_mode, || {
+            let properties = deserialize_properties(json!({ options.property_id: property }));
+
+            (properties, take_property_warnings())
+        });
+
+        Ok(PageProperty {
+            property: properties?
+                .remove(options.property_id)
+                .ok_or(Error::UnexpectedType)?,
+            warnings,
+        })
     }
 }
 
-pub struct BlockChildren {
-    http_client: Arc<reqwest::Client>,
-    request_handler: Arc<Callback>,
+/// A property retrieved with `Pages::property`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageProperty {
+    pub property: Property,
+    /// What had to be patched up or skipped in `DeserializationMode::Lenient`, see
+    /// `Page::warnings`.
+    pub warnings: Vec<PropertyWarning>,
 }
 
-pub struct BlockChildrenListOptions<'a> {
-    pub block_id: &'a str,
+/// One page of a `/v1/pages/{id}/properties/{property_id}` response for a paginated property.
+#[derive(Deserialize)]
+struct PropertyItemList {
+    results: Vec<Value>,
+    next_cursor: Option<String>,
+    has_more: bool,
+    property_item: Value,
 }
 
-impl BlockChildren {
-    pub async fn list<'a>(
-        self,
-        options: BlockChildrenListOptions<'a>,
-    ) -> Result<QueryResponse<Block>> {
+/// Turns a paginated `property_item` and all of its `results` back into a property value.
+fn merge_property_items(mut property_item: Value, items: Vec<Value>) -> Value {
+    let kind = property_item["type"]
+        .as_str()
+        .unwrap_or_default()
+        .to_owned();
+
+    // Rollups are computed by Notion, the results are only the values they were computed from
+    if kind != "rollup" {
+        property_item[kind.as_str()] = items
+            .into_iter()
+            .map(|mut item| item[kind.as_str()].take())
+            .collect();
+    }
+
+    property_item
+}
+
+#[derive(Clone)]
+pub struct Blocks {
+    http_client: Arc<reqwest::Client>,
+    request_handler: Arc<RequestHandler>,
+    deserialization_mode: DeserializationMode,
+    api_version: ApiVersion,
+    /// Shared with `Databases`, for the rows of child databases.
+    data_sources: Arc<Mutex<HashMap<DatabaseId, String>>>,
+}
+
+pub struct BlockOptions {
+    pub block_id: BlockId,
+}
+
+pub struct BlockUpdateOptions {
+    pub block_id: BlockId,
+    /// The new content, which has to be of the same type. Its children are ignored.
+    pub block: BlockType,
+}
+
+/// Which descendants `Blocks::fetch_tree` fetches.
+pub struct BlockTreeOptions {
+    pub block_id: BlockId,
+    /// Levels of descendants to fetch, `None` for all of them. `Some(1)` only fetches the direct
+    /// children of `block_id`.
+    pub max_depth: Option<usize>,
+    /// Maximum number of requests in flight at once.
+    pub concurrency: usize,
+    /// Whether to fetch the content of child pages.
+    pub child_pages: bool,
+    /// Whether to fetch the rows of child databases (as `ChildPage` blocks). Databases the
+    /// integration can't query are skipped with a warning, and keep `children: None`.
+    pub child_databases: bool,
+}
+
+impl Default for BlockTreeOptions {
+    fn default() -> Self {
+        BlockTreeOptions {
+            block_id: BlockId::default(),
+            max_depth: None,
+            // Notion allows an average of three requests per second
+            concurrency: 3,
+            child_pages: false,
+            child_databases: false,
+        }
+    }
+}
+
+impl Blocks {
+    pub async fn retrieve(&self, options: BlockOptions) -> Result<Block> {
+        let url = format!(
+            "https://api.notion.com/v1/blocks/{block_id}",
+            block_id = options.block_id
+        );
+
+        let mut request = self.http_client.get(url);
+
+        let response = (self.request_handler)(&mut request).await?;
+
+        match response.error_for_status_ref() {
+            Ok(_) => try_to_parse_response(response, self.deserialization_mode).await,
+            Err(error) => {
+                let body = response.json::<Value>().await?;
+                Err(Error::Http(error, Some(body)))
+            }
+        }
+    }
+
+    pub async fn update(&self, options: BlockUpdateOptions) -> Result<Block> {
+        let url = format!(
+            "https://api.notion.com/v1/blocks/{block_id}",
+            block_id = options.block_id
+        );
+
+        let mut block = options.block;
+        if let Some(children) = block.children_mut() {
+            *children = None;
+        }
+        let mut body = append_json(&block);
+        if let Value::Object(object) = &mut body {
+            object.remove("type");
+        }
+
+        let mut request = self.http_client.patch(url).json(&body);
+
+        let response = (self.request_handler)(&mut request).await?;
+
+        match response.error_for_status_ref() {
+            Ok(_) => try_to_parse_response(response, self.deserialization_mode).await,
+            Err(error) => {
+                let body = response.json::<Value>().await?;
+                Err(Error::Http(error, Some(body)))
+            }
+        }
+    }
+
+    /// Moves a block to the trash and returns it.
+    pub async fn delete(&self, options: BlockOptions) -> Result<Block> {
+        let url = format!(
+            "https://api.notion.com/v1/blocks/{block_id}",
+            block_id = options.block_id
+        );
+
+        let mut request = self.http_client.delete(url);
+
+        let response = (self.request_handler)(&mut request).await?;
+
+        match response.error_for_status_ref() {
+            Ok(_) => try_to_parse_response(response, self.deserialization_mode).await,
+            Err(error) => {
+                let body = response.json::<Value>().await?;
+                Err(Error::Http(error, Some(body)))
+            }
+        }
+    }
+
+    pub fn children(&self) -> BlockChildren {
+        BlockChildren {
+            http_client: self.http_client.clone(),
+            request_handler: self.request_handler.clone(),
+            deserialization_mode: self.deserialization_mode,
+        }
+    }
+
+    /// Fetches all descendants of a block (or page) and fills in their `children`, level by
+    /// level so that no more than `options.concurrency` requests are in flight.
+    pub async fn fetch_tree(&self, options: BlockTreeOptions) -> Result<Vec<Block>> {
+        let mut tree = self.list_all(options.block_id.to_string()).await?;
+        let mut level = tree.iter_mut().collect::<Vec<_>>();
+        let mut depth = 1;
+
+        while !level.is_empty() && options.max_depth.is_none_or(|max| depth < max) {
+            let parents = level
+                .into_iter()
+                .filter_map(|block| {
+                    let descend = match block.block {
+                        BlockType::ChildPage { .. } => options.child_pages && block.has_children,
+                        // Child databases never report children, so all of them are queried
+                        BlockType::ChildDatabase { .. } => options.child_databases,
+                        // Blocks like synced blocks have children but nowhere to put them
+                        _ => block.has_children && block.block.children_mut().is_some(),
+                    };
+
+                    descend.then_some(block)
+                })
+                .collect::<Vec<_>>();
+
+            let requests = parents
+                .iter()
+                .map(|block| {
+                    let id = block.id.clone();
+
+                    async move {
+                        match block.block {
+                            BlockType::ChildDatabase { .. } => {
+                                match self.list_rows(id.clone()).await {
+                                    // E.g. linked databases, or ones not shared with the integration
+                                    Err(Error::Http(error, _))
+                                        if error.status()
+                                            == Some(reqwest::StatusCode::NOT_FOUND) =>
+                                    {
+                                        log::warn!("Skipping rows of database {id}: {error}");
+                                        Ok(None)
+                                    }
+                                    Err(error @ Error::MissingDataSource(_)) => {
+                                        log::warn!("Skipping rows of database {id}: {error}");
+                                        Ok(None)
+                                    }
+                                    rows => rows.map(Some),
+                                }
+                            }
+                            _ => self.list_all(id).await.map(Some),
+                        }
+                    }
+                })
+                .collect::<Vec<_>>();
+            let children = futures_util::stream::iter(requests)
+                .buffered(options.concurrency.max(1))
+                .collect::<Vec<_>>()
+                .await;
+
+            level = Vec::new();
+            for (parent, children) in parents.into_iter().zip(children) {
+                // Skipped databases keep `children: None`, as if they weren't fetched
+                if let (Some(slot), Some(children)) = (parent.block.children_mut(), children?) {
+                    level.extend(slot.insert(children).iter_mut());
+                }
+            }
+            depth += 1;
+        }
+
+        Ok(tree)
+    }
+
+    async fn list_all(&self, block_id: String) -> Result<Vec<Block>> {
+        let url = format!("https://api.notion.com/v1/blocks/{block_id}/children");
+        let mut blocks = Vec::new();
+        let mut cursor = None;
+
+        loop {
+            let mut request = self.http_client.get(&url).query(&[("page_size", "100")]);
+            if let Some(cursor) = &cursor {
+                request = request.query(&[("start_cursor", cursor)]);
+            }
+
+            let response = (self.request_handler)(&mut request).await?;
+
+            let page = match response.error_for_status_ref() {
+                Ok(_) => {
+                    try_to_parse_response::<QueryResponse<Block>>(
+                        response,
+                        self.deserialization_mode,
+                    )
+                    .await?
+                }
+                Err(error) => {
+                    let body = response.json::<Value>().await?;
+                    return Err(Error::Http(error, Some(body)));
+                }
+            };
+
+            blocks.extend(page.results);
+            match page.next_cursor {
+                Some(next_cursor) if page.has_more => cursor = Some(next_cursor),
+                _ => return Ok(blocks),
+            }
+        }
+    }
+
+    async fn list_rows(&self, database_id: String) -> Result<Vec<Block>> {
+        let databases = Databases {
+            http_client: self.http_client.clone(),
+            request_handler: self.request_handler.clone(),
+            deserialization_mode: self.deserialization_mode,
+            api_version: self.api_version,
+            data_sources: self.data_sources.clone(),
+        };
+        let mut rows = Vec::new();
+        let mut start_cursor = None;
+
+        loop {
+            let page = databases
+                .query(DatabaseQueryOptions {
+                    database_id: DatabaseId::new_unchecked(database_id.as_str()),
+                    start_cursor,
+                    ..Default::default()
+                })
+                .await?;
+
+            rows.extend(page.results.into_iter().map(|row| {
+                let title = row
+                    .properties
+                    .values()
+                    .find_map(|property| match property {
+                        Property::Title { title, .. } => Some(mapping::plain_text(title)),
+                        _ => None,
+                    })
+                    .unwrap_or_default();
+
+                Block {
+                    id: row.id,
+                    parent: row.parent,
+                    created_time: row.created_time,
+                    last_edited_time: row.last_edited_time,
+                    // Unknown without it, like for blocks made with `Block::new`
+                    last_edited_by: row
+                        .last_edited_by
+                        .unwrap_or(PartialUser { id: String::new() }),
+                    created_by: row.created_by,
+                    // Rows don't say whether they have content, so assume they do
+                    has_children: true,
+                    archived: row.archived,
+                    in_trash: row.in_trash,
+                    block: BlockType::ChildPage {
+                        child_page: ChildPage {
+                            title,
+                            children: None,
+                        },
+                    },
+                }
+            }));
+
+            match page.next_cursor {
+                Some(next_cursor) if page.has_more => start_cursor = Some(next_cursor),
+                _ => return Ok(rows),
+            }
+        }
+    }
+}
+
+pub struct BlockChildren {
+    http_client: Arc<reqwest::Client>,
+    request_handler: Arc<RequestHandler>,
+    deserialization_mode: DeserializationMode,
+}
+
+pub struct BlockChildrenListOptions {
+    pub block_id: BlockId,
+}
+
+pub struct BlockChildrenAppendOptions {
+    pub block_id: BlockId,
+    pub children: Vec<BlockType>,
+    /// The child to insert the blocks after, `None` to add them at the end.
+    pub after: Option<BlockId>,
+}
+
+impl BlockChildren {
+    pub async fn list(self, options: BlockChildrenListOptions) -> Result<QueryResponse<Block>> {
         let url = format!(
             "https://api.notion.com/v1/blocks/{block_id}/children",
             block_id = options.block_id
@@ -291,39 +1199,154 @@ impl BlockChildren {
         let response = (self.request_handler)(&mut request).await?;
 
         match response.error_for_status_ref() {
-            Ok(_) => Ok(response.json().await?),
+            Ok(_) => try_to_parse_response(response, self.deserialization_mode).await,
             Err(error) => {
                 let body = response.json::<Value>().await?;
                 Err(Error::Http(error, Some(body)))
             }
         }
     }
+
+    /// Appends `children` to a block and returns the created blocks.
+    ///
+    /// Notion only accepts two levels of nesting per request, so nested children are appended with
+    /// follow-up requests, except for tables and column lists which have to be created with them.
+    pub async fn append(&self, options: BlockChildrenAppendOptions) -> Result<Vec<Block>> {
+        self.append_children(
+            options.block_id.to_string(),
+            options.children,
+            options.after.map(|after| after.to_string()),
+        )
+        .await
+    }
+
+    fn append_children(
+        &self,
+        block_id: String,
+        children: Vec<BlockType>,
+        mut after: Option<String>,
+    ) -> BoxFuture<'_, Result<Vec<Block>>> {
+        Box::pin(async move {
+            let url = format!("https://api.notion.com/v1/blocks/{block_id}/children");
+            let mut appended = Vec::new();
+
+            // Notion accepts at most 100 blocks per request
+            for chunk in children.chunks(100) {
+                let (body, nested): (Vec<Value>, Vec<Option<Vec<Block>>>) = chunk
+                    .iter()
+                    .map(|block| {
+                        let mut block = block.clone();
+                        let nested = match block {
+                            BlockType::Table { .. } | BlockType::ColumnList { .. } => None,
+                            _ => block.children_mut().and_then(Option::take),
+                        };
+
+                        (append_json(&block), nested)
+                    })
+                    .unzip();
+
+                let mut body = json!({ "children": body });
+                if let Some(after) = &after {
+                    body["after"] = json!(after);
+                }
+
+                let mut request = self.http_client.patch(&url).json(&body);
+
+                let response = (self.request_handler)(&mut request).await?;
+
+                let created = match response.error_for_status_ref() {
+                    Ok(_) => {
+                        try_to_parse_response::<QueryResponse<Block>>(
+                            response,
+                            self.deserialization_mode,
+                        )
+                        .await?
+                    }
+                    Err(error) => {
+                        let body = response.json::<Value>().await?;
+                        return Err(Error::Http(error, Some(body)));
+                    }
+                };
+
+                // The next chunk goes after the last block of this one
+                if after.is_some() {
+                    after = created.results.last().map(|block| block.id.clone());
+                }
+
+                for (mut block, nested) in created.results.into_iter().zip(nested) {
+                    if let Some(nested) = nested.filter(|nested| !nested.is_empty()) {
+                        let children = self
+                            .append_children(
+                                block.id.clone(),
+                                nested.into_iter().map(|child| child.block).collect(),
+                                None,
+                            )
+                            .await?;
+
+                        if let Some(slot) = block.block.children_mut() {
+                            *slot = Some(children);
+                        }
+                    }
+
+                    appended.push(block);
+                }
+            }
+
+            Ok(appended)
+        })
+    }
 }
 
 #[derive(Clone)]
 pub struct Databases {
     http_client: Arc<reqwest::Client>,
-    request_handler: Arc<Callback>,
+    request_handler: Arc<RequestHandler>,
+    deserialization_mode: DeserializationMode,
+    api_version: ApiVersion,
+    /// The data source queried for each database since `ApiVersion::V2025_09_03`.
+    data_sources: Arc<Mutex<HashMap<DatabaseId, String>>>,
 }
 
 impl Databases {
-    pub async fn query<'a>(
-        &self,
-        options: DatabaseQueryOptions<'a>,
-    ) -> Result<QueryResponse<Page>> {
+    pub async fn retrieve(&self, options: DatabaseOptions) -> Result<Database> {
         let url = format!(
-            "https://api.notion.com/v1/databases/{database_id}/query",
+            "https://api.notion.com/v1/databases/{database_id}",
             database_id = options.database_id
         );
 
-        let mut request = self.http_client.post(url);
+        let mut request = self.http_client.get(url);
 
-        let json = if let Some(filter) = options.filter {
-            Some(json!({ "filter": filter }))
-        } else {
-            None
+        let response = (self.request_handler)(&mut request).await?;
+
+        match response.error_for_status_ref() {
+            Ok(_) => try_to_parse_response(response, self.deserialization_mode).await,
+            Err(error) => {
+                let body = response.json::<Value>().await?;
+                Err(Error::Http(error, Some(body)))
+            }
+        }
+    }
+
+    /// Queries the rows of a database.
+    ///
+    /// Since `ApiVersion::V2025_09_03` rows belong to data sources, so this queries the first
+    /// data source of the database, which is looked up once per database.
+    pub async fn query(&self, options: DatabaseQueryOptions) -> Result<QueryResponse<Page>> {
+        let url = match self.api_version {
+            ApiVersion::V2022_06_28 => format!(
+                "https://api.notion.com/v1/databases/{database_id}/query",
+                database_id = options.database_id
+            ),
+            ApiVersion::V2025_09_03 => format!(
+                "https://api.notion.com/v1/data_sources/{data_source_id}/query",
+                data_source_id = self.data_source_id(&options.database_id).await?
+            ),
         };
 
+        let mut request = self.http_client.post(url);
+
+        let json = options.filter.map(|filter| json!({ "filter": filter }));
+
         let json = if let Some(sorts) = options.sorts {
             if let Some(mut json) = json {
                 json.as_object_mut()
@@ -359,24 +1382,51 @@ impl Databases {
         let response = (self.request_handler)(&mut request).await?;
 
         match response.error_for_status_ref() {
-            Ok(_) => try_to_parse_response(response).await,
+            Ok(_) => try_to_parse_response(response, self.deserialization_mode).await,
             Err(error) => {
-                let body = try_to_parse_response::<Value>(response).await?;
+                let body =
+                    try_to_parse_response::<Value>(response, self.deserialization_mode).await?;
                 Err(Error::Http(error, Some(body)))
             }
         }
     }
+
+    async fn data_source_id(&self, database_id: &DatabaseId) -> Result<String> {
+        if let Some(id) = self.data_sources.lock().unwrap().get(database_id) {
+            return Ok(id.clone());
+        }
+
+        let database = self
+            .retrieve(DatabaseOptions {
+                database_id: database_id.clone(),
+            })
+            .await?;
+        let id = database
+            .data_sources
+            .and_then(|data_sources| data_sources.into_iter().next())
+            .ok_or_else(|| Error::MissingDataSource(database_id.to_string()))?
+            .id;
+
+        self.data_sources
+            .lock()
+            .unwrap()
+            .insert(database_id.clone(), id.clone());
+
+        Ok(id)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[cfg(feature = "request")]
     #[tokio::test]
     async fn check_database_query() {
         let databases = Client::new()
             .api_key("secret_FuhJkAoOVZlk8YUT9ZOeYqWBRRZN6OMISJwhb4dTnud")
             .build()
+            .unwrap()
             .search::<Database>(SearchOptions {
                 filter: Some(json!(
                     {
@@ -394,25 +1444,845 @@ mod tests {
         println!("{databases:#?}");
     }
 
+    #[cfg(feature = "request")]
     #[tokio::test]
     async fn test_blocks() {
         let blocks = Client::new()
             .api_key("secret_FuhJkAoOVZlk8YUT9ZOeYqWBRRZN6OMISJwhb4dTnud")
             .build()
+            .unwrap()
             .blocks
             .children()
             .list(BlockChildrenListOptions {
-                block_id: "0d253ab0f751443aafb9bcec14012897",
+                block_id: BlockId::new_unchecked("0d253ab0f751443aafb9bcec14012897"),
             })
             .await;
 
         println!("{blocks:#?}")
     }
+
+    /// A client that answers every request with `handler` instead of sending it.
+    #[cfg(feature = "request")]
+    fn mock_client(handler: impl Fn(&reqwest::Request) -> Value + Send + Sync + 'static) -> Client {
+        let handler = Arc::new(handler);
+
+        Client::new()
+            .api_key("secret")
+            .custom_request(move |request| {
+                let handler = handler.clone();
+
+                Box::pin(async move {
+                    let request = request
+                        .try_clone()
+                        .expect("request to be clonable")
+                        .build()?;
+                    let body = handler(&request);
+                    let mut response = http::Response::new(body.to_string());
+                    // Errors have the status of their response in their body
+                    if body["object"] == "error" {
+                        *response.status_mut() =
+                            http::StatusCode::from_u16(body["status"].as_u64().unwrap() as u16)
+                                .unwrap();
+                    }
+
+                    Ok(reqwest::Response::from(response))
+                })
+            })
+            .build()
+            .unwrap()
+    }
+
+    #[cfg(feature = "request")]
+    #[test]
+    fn build_reports_configuration_errors() {
+        assert!(matches!(Client::new().build(), Err(Error::MissingApiKey)));
+        assert!(matches!(
+            Client::new().api_key("secret\n").build(),
+            Err(Error::Header(_))
+        ));
+        assert!(Client::new()
+            .api_key("secret")
+            .connect_timeout(Duration::from_secs(5))
+            .timeout(Duration::from_secs(30))
+            .proxy(reqwest::Proxy::all("http://proxy.internal:3128").unwrap())
+            .user_agent("audit-bot/1.0")
+            .pool_max_idle_per_host(4)
+            .build()
+            .is_ok());
+    }
+
+    #[cfg(feature = "request")]
+    #[tokio::test]
+    async fn token_provider_is_asked_for_every_request() {
+        struct Rotating(std::sync::atomic::AtomicUsize);
+
+        #[async_trait::async_trait]
+        impl TokenProvider for Rotating {
+            async fn token(&self) -> Result<String> {
+                let count = self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                Ok(format!("token-{count}"))
+            }
+        }
+
+        let client = Client::new()
+            .token_provider(Rotating(Default::default()))
+            .custom_request(|request| {
+                Box::pin(async move {
+                    let request = request.try_clone().unwrap().build()?;
+                    let authorization = request.headers()["Authorization"].to_str().unwrap();
+                    let body =
+                        json!({ "object": "user", "id": authorization, "type": "bot", "bot": {} });
+
+                    Ok(reqwest::Response::from(http::Response::new(
+                        body.to_string(),
+                    )))
+                })
+            })
+            .build()
+            .unwrap();
+
+        assert_eq!(client.users.me().await.unwrap().id(), "Bearer token-0");
+        assert_eq!(client.users.me().await.unwrap().id(), "Bearer token-1");
+    }
+
+    #[cfg(feature = "request")]
+    #[tokio::test]
+    async fn token_provider_errors_fail_the_request() {
+        struct Expired;
+
+        #[async_trait::async_trait]
+        impl TokenProvider for Expired {
+            async fn token(&self) -> Result<String> {
+                Err(Error::Io(std::io::Error::other("refresh token revoked")))
+            }
+        }
+
+        let client = Client::new()
+            .token_provider(Expired)
+            .custom_request(|_| panic!("no request to be sent without a token"))
+            .build()
+            .unwrap();
+
+        assert!(matches!(client.users.me().await, Err(Error::Io(_))));
+    }
+
+    #[cfg(feature = "request")]
+    fn block_json(id: &str, kind: &str, has_children: bool) -> Value {
+        let content = match kind {
+            "child_page" => json!({ "title": id }),
+            _ => json!({ "color": "default", "rich_text": [] }),
+        };
+
+        json!({
+            "object": "block",
+            "id": id,
+            "parent": { "type": "page_id", "page_id": "root" },
+            "created_time": "2022-03-01T19:05:00.000Z",
+            "last_edited_time": "2022-07-06T20:25:00.000Z",
+            "created_by": { "id": "ee5f0f84-409a-440f-983a-a5315961c6e4" },
+            "last_edited_by": { "id": "ee5f0f84-409a-440f-983a-a5315961c6e4" },
+            "has_children": has_children,
+            "archived": false,
+            "type": kind,
+            kind: content
+        })
+    }
+
+    #[cfg(feature = "request")]
+    #[tokio::test]
+    async fn fetch_tree_fills_children() {
+        let client = mock_client(|request| {
+            let results = match request.url().path() {
+                "/v1/blocks/root/children" => vec![
+                    block_json("a", "paragraph", true),
+                    block_json("page", "child_page", true),
+                ],
+                "/v1/blocks/a/children" => vec![block_json("b", "toggle", true)],
+                "/v1/blocks/b/children" => vec![block_json("c", "paragraph", false)],
+                path => panic!("unexpected request to {path}"),
+            };
+
+            json!({ "object": "list", "results": results, "has_more": false, "next_cursor": null })
+        });
+
+        let tree = client
+            .blocks
+            .fetch_tree(BlockTreeOptions {
+                block_id: BlockId::new_unchecked("root"),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let b = &tree[0].block.children().unwrap()[0];
+        assert_eq!(b.id, "b");
+        assert_eq!(b.block.children().unwrap()[0].id, "c");
+        assert_eq!(tree[1].block.children(), None);
+
+        let shallow = client
+            .blocks
+            .fetch_tree(BlockTreeOptions {
+                block_id: BlockId::new_unchecked("root"),
+                max_depth: Some(2),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            shallow[0].block.children().unwrap()[0].block.children(),
+            None
+        );
+    }
+
+    #[cfg(feature = "request")]
+    #[tokio::test]
+    async fn fetch_tree_lists_database_rows() {
+        let client = mock_client(|request| match request.url().path() {
+            "/v1/blocks/root/children" => {
+                let databases = ["db", "linked"].map(|id| {
+                    let mut database = block_json(id, "paragraph", false);
+                    database["type"] = json!("child_database");
+                    database["child_database"] = json!({ "title": "Tasks" });
+                    database
+                });
+
+                json!({ "object": "list", "results": databases, "has_more": false, "next_cursor": null })
+            }
+            "/v1/databases/linked/query" => json!({
+                "object": "error",
+                "status": 404,
+                "code": "object_not_found",
+                "message": "Could not find database with ID: linked."
+            }),
+            "/v1/databases/db/query" => {
+                let mut row = page_with_properties(json!({}));
+                row["last_edited_by"] = json!({ "object": "user", "id": "editor" });
+
+                json!({ "object": "list", "results": [row], "has_more": false, "next_cursor": null })
+            }
+            path => panic!("unexpected request to {path}"),
+        });
+
+        let tree = client
+            .blocks
+            .fetch_tree(BlockTreeOptions {
+                block_id: BlockId::new_unchecked("root"),
+                child_databases: true,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let row = &tree[0].block.children().unwrap()[0];
+        assert_eq!(row.created_by.id, "ee5f0f84-409a-440f-983a-a5315961c6e4");
+        assert_eq!(row.last_edited_by.id, "editor");
+        assert_eq!(tree[1].block.children(), None);
+    }
+
+    #[cfg(feature = "request")]
+    #[tokio::test]
+    async fn property_merges_all_pages_of_items() {
+        let client = mock_client(|request| {
+            if request.url().path() == "/v1/pages/page/properties/num" {
+                return json!({ "object": "property_item", "id": "num", "type": "number", "number": "three" });
+            }
+            assert_eq!(request.url().path(), "/v1/pages/page/properties/rel");
+
+            let (results, next_cursor) = match request.url().query() {
+                None => (vec!["a", "b"], json!("cursor")),
+                Some("start_cursor=cursor") => (vec!["c"], Value::Null),
+                Some(query) => panic!("unexpected query {query}"),
+            };
+
+            json!({
+                "object": "list",
+                "results": results
+                    .into_iter()
+                    .map(|id| json!({ "object": "property_item", "id": "rel", "type": "relation", "relation": { "id": id } }))
+                    .collect::<Vec<_>>(),
+                "next_cursor": next_cursor,
+                "has_more": !next_cursor.is_null(),
+                "type": "property_item",
+                "property_item": { "id": "rel", "next_url": null, "type": "relation", "relation": {} }
+            })
+        });
+
+        let property = client
+            .pages
+            .property(PagePropertyOptions {
+                page_id: PageId::new_unchecked("page"),
+                property_id: "rel",
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            property,
+            PageProperty {
+                property: Property::Relation {
+                    id: "rel".to_owned(),
+                    relation: ["a", "b", "c"]
+                        .map(|id| PartialPage { id: id.to_owned() })
+                        .to_vec(),
+                    has_more: false,
+                },
+                warnings: vec![],
+            }
+        );
+
+        let unparsable = client
+            .pages
+            .property(PagePropertyOptions {
+                page_id: PageId::new_unchecked("page"),
+                property_id: "num",
+            })
+            .await
+            .unwrap();
+
+        assert!(matches!(unparsable.property, Property::Unsupported(_)));
+        assert!(matches!(
+            &unparsable.warnings[..],
+            [PropertyWarning { property, patch: PropertyPatch::Unsupported(_), .. }] if property == "num"
+        ));
+    }
+
+    #[test]
+    fn users_are_people_or_bots() {
+        let users = serde_json::from_value::<Vec<User>>(json!([
+            {
+                "object": "user",
+                "id": "d40e767c-d7af-4b18-a86d-55c61f1e39a4",
+                "type": "person",
+                "person": { "email": "avo@example.org" },
+                "name": "Avocado Lovelace",
+                "avatar_url": null
+            },
+            {
+                "object": "user",
+                "id": "9188c6a5-7381-452f-b3dc-d4865aa89bdf",
+                "name": "Test Integration",
+                "avatar_url": null,
+                "type": "bot",
+                "bot": {
+                    "owner": { "type": "workspace", "workspace": true },
+                    "workspace_name": "Ada's Notion Workspace"
+                }
+            },
+            {
+                "object": "user",
+                "id": "0a2b3c4d-7381-452f-b3dc-d4865aa89bdf",
+                "type": "bot",
+                "bot": {}
+            }
+        ]))
+        .unwrap();
+
+        assert_eq!(users[0].name(), Some("Avocado Lovelace"));
+        assert!(matches!(
+            &users[1],
+            User::Bot {
+                bot: Bot {
+                    owner: Some(BotOwner::Workspace(Workspace { workspace: true })),
+                    workspace_name: Some(_),
+                },
+                ..
+            }
+        ));
+        assert_eq!(users[2].name(), None);
+    }
+
+    #[cfg(feature = "request")]
+    #[tokio::test]
+    async fn large_files_are_uploaded_in_parts() {
+        let parts = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sent = parts.clone();
+        let client = mock_client(move |request| {
+            let body = request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .unwrap_or_default();
+            let upload = |status: &str| {
+                json!({
+                    "object": "file_upload",
+                    "id": "upload",
+                    "status": status,
+                    "filename": "chart.png",
+                    "content_type": "image/png",
+                    "content_length": null,
+                    "expiry_time": "2025-01-01T01:00:00.000Z",
+                    "number_of_parts": { "total": 3, "sent": 0 },
+                    "created_time": "2025-01-01T00:00:00.000Z",
+                    "last_edited_time": "2025-01-01T00:00:00.000Z"
+                })
+            };
+
+            match request.url().path() {
+                "/v1/file_uploads" => {
+                    let body = serde_json::from_slice::<Value>(body).unwrap();
+                    assert_eq!(body["mode"], "multi_part");
+                    assert_eq!(body["number_of_parts"], 3);
+
+                    upload("pending")
+                }
+                "/v1/file_uploads/upload/send" => {
+                    let content_type = request.headers()["Content-Type"].to_str().unwrap();
+                    assert!(content_type.starts_with("multipart/form-data; boundary="));
+                    let head = String::from_utf8_lossy(&body[..250]);
+                    let part_number = head.split("\r\n").nth(3).unwrap().to_owned();
+                    assert!(head.contains(
+                        "name=\"file\"; filename=\"chart.png\"\r\nContent-Type: image/png\r\n"
+                    ));
+                    sent.lock().unwrap().push((part_number, body.len()));
+
+                    upload("pending")
+                }
+                "/v1/file_uploads/upload/complete" => upload("uploaded"),
+                path => panic!("unexpected request {path}"),
+            }
+        });
+
+        let content = vec![0; 21 * 1024 * 1024];
+        let upload = client
+            .file_uploads
+            .upload(FileUploadContentOptions {
+                filename: "chart.png",
+                content_type: "image/png",
+                content: &content,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(upload.status, FileUploadStatus::Uploaded);
+        let parts = parts.lock().unwrap();
+        assert_eq!(
+            parts
+                .iter()
+                .map(|(part, _)| part.as_str())
+                .collect::<Vec<_>>(),
+            ["1", "2", "3"]
+        );
+        assert!(parts[2].1 > 1024 * 1024 && parts[2].1 < 2 * 1024 * 1024);
+        assert_eq!(
+            serde_json::to_value(BlockType::Image {
+                image: upload.file()
+            })
+            .unwrap(),
+            json!({ "type": "image", "image": { "type": "file_upload", "file_upload": { "id": "upload" } } })
+        );
+    }
+
+    #[test]
+    fn multipart_bodies_keep_names_and_types_in_their_headers() {
+        let options = |content_type| FileUploadSendOptions {
+            file_upload_id: "upload",
+            filename: "Q3 \"final\"\r\n.pdf",
+            content_type,
+            content: b"%PDF",
+            part_number: None,
+        };
+
+        let (_, body) = multipart_body(&options("application/pdf")).unwrap();
+
+        assert!(String::from_utf8(body).unwrap().contains(
+            "filename=\"Q3 %22final%22%0D%0A.pdf\"\r\nContent-Type: application/pdf\r\n"
+        ));
+        assert!(matches!(
+            multipart_body(&options("application/pdf\r\nX-Injected: 1")),
+            Err(Error::InvalidContentType(_))
+        ));
+    }
+
+    #[cfg(feature = "request")]
+    #[tokio::test]
+    async fn comments_are_listed_and_created() {
+        let client = mock_client(|request| {
+            let comment = |id: &str| {
+                json!({
+                    "object": "comment",
+                    "id": id,
+                    "parent": { "type": "page_id", "page_id": "page" },
+                    "discussion_id": "discussion",
+                    "created_time": "2022-07-15T16:52:00.000Z",
+                    "last_edited_time": "2022-07-15T19:16:00.000Z",
+                    "created_by": { "object": "user", "id": "bot" },
+                    "rich_text": [{
+                        "type": "text",
+                        "text": { "content": "Looks good", "link": null },
+                        "annotations": {
+                            "bold": false, "italic": false, "strikethrough": false,
+                            "underline": false, "code": false, "color": "default"
+                        },
+                        "plain_text": "Looks good",
+                        "href": null
+                    }]
+                })
+            };
+
+            match (request.method().as_str(), request.url().query()) {
+                ("GET", Some("block_id=page&page_size=100")) => json!({
+                    "object": "list",
+                    "results": [comment("a")],
+                    "next_cursor": "cursor",
+                    "has_more": true
+                }),
+                ("GET", Some("block_id=page&page_size=100&start_cursor=cursor")) => json!({
+                    "object": "list",
+                    "results": [comment("b")],
+                    "next_cursor": null,
+                    "has_more": false
+                }),
+                ("POST", None) => {
+                    let body = serde_json::from_slice::<Value>(
+                        request.body().and_then(|body| body.as_bytes()).unwrap(),
+                    )
+                    .unwrap();
+                    assert_eq!(body["discussion_id"], "discussion");
+                    assert_eq!(
+                        body["rich_text"][0],
+                        json!({
+                            "type": "text",
+                            "text": { "content": "Looks good" },
+                            "annotations": {
+                                "bold": false, "italic": false, "strikethrough": false,
+                                "underline": false, "code": false, "color": "default"
+                            }
+                        })
+                    );
+
+                    comment("c")
+                }
+                request => panic!("unexpected request {request:?}"),
+            }
+        });
+
+        let comments = client
+            .comments
+            .list(CommentListOptions {
+                block_id: BlockId::new_unchecked("page"),
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            comments
+                .iter()
+                .map(|comment| comment.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["a", "b"]
+        );
+
+        let reply = client
+            .comments
+            .create(CommentCreateOptions {
+                target: CommentTarget::Discussion((&comments[0].discussion_id).into()),
+                rich_text: comments[0].rich_text.clone(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(reply.id, "c");
+    }
+
+    #[test]
+    fn version_specific_fields_are_kept_under_any_api_version() {
+        let database = json!({
+            "object": "database",
+            "id": "248104cd-477e-80fd-b757-e945d38000bd",
+            "title": [],
+            "description": [],
+            "url": "https://www.notion.so/248104cd477e80fdb757e945d38000bd",
+            "parent": { "type": "page_id", "page_id": "255104cd-477e-808c-b279-d39ab803a7d2" },
+            "created_time": "2025-08-07T10:11:07.504Z",
+            "last_edited_time": "2025-08-10T15:53:11.386Z",
+            "last_edited_by": { "object": "user", "id": "ee5f0f84-409a-440f-983a-a5315961c6e4" },
+            "icon": null,
+            "cover": null,
+            "in_trash": false,
+            "is_inline": false,
+            "data_sources": [{ "id": "248104cd-477e-80af-bc30-000bd28de8f9", "name": "Tasks" }]
+        });
+
+        // E.g. a backup written with a newer version, read back by a client with the default one
+        let database = serde_json::from_value::<Database>(database).unwrap();
+
+        assert_eq!(database.in_trash, Some(false));
+        assert_eq!(
+            database.data_sources,
+            Some(vec![DataSourceReference {
+                id: "248104cd-477e-80af-bc30-000bd28de8f9".to_owned(),
+                name: "Tasks".to_owned()
+            }])
+        );
+    }
+
+    #[cfg(feature = "request")]
+    #[tokio::test]
+    async fn search_all_follows_cursors_and_tells_objects_apart() {
+        let client = mock_client(|request| {
+            let body = serde_json::from_slice::<Value>(
+                request.body().and_then(|body| body.as_bytes()).unwrap(),
+            )
+            .unwrap();
+            assert_eq!(
+                body["sort"],
+                json!({ "timestamp": "last_edited_time", "direction": "descending" })
+            );
+
+            if body["start_cursor"].is_null() {
+                let mut page = page_with_properties(json!({}));
+                page["object"] = json!("page");
+
+                json!({ "object": "list", "results": [page], "next_cursor": "cursor", "has_more": true })
+            } else {
+                assert_eq!(body["start_cursor"], "cursor");
+                let database = json!({
+                    "object": "database",
+                    "id": "248104cd-477e-80fd-b757-e945d38000bd",
+                    "title": [],
+                    "description": [],
+                    "properties": {},
+                    "url": "https://www.notion.so/248104cd477e80fdb757e945d38000bd",
+                    "parent": { "type": "workspace", "workspace": true },
+                    "created_time": "2025-08-07T10:11:07.504Z",
+                    "last_edited_time": "2025-08-10T15:53:11.386Z",
+                    "last_edited_by": { "object": "user", "id": "ee5f0f84-409a-440f-983a-a5315961c6e4" },
+                    "icon": null,
+                    "cover": null,
+                    "archived": false,
+                    "is_inline": false
+                });
+
+                let data_source = json!({
+                    "object": "data_source",
+                    "id": "248104cd-477e-80af-bc30-000bd28de8f9",
+                    "title": [],
+                    "properties": {},
+                    "parent": { "type": "database_id", "database_id": "248104cd-477e-80fd-b757-e945d38000bd" },
+                    "created_time": "2025-08-07T10:11:07.504Z",
+                    "last_edited_time": "2025-08-10T15:53:11.386Z"
+                });
+                let unknown = json!({ "object": "form", "id": "1a2b" });
+
+                json!({
+                    "object": "list",
+                    "results": [database, data_source, unknown],
+                    "next_cursor": null,
+                    "has_more": false
+                })
+            }
+        });
+
+        let results = client
+            .search_all(SearchAllOptions {
+                sort: Some(SearchSort::LastEditedDescending),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        assert!(matches!(results[0], SearchResult::Page(_)));
+        assert!(matches!(results[1], SearchResult::Database(_)));
+        assert_eq!(results[1].id(), "248104cd-477e-80fd-b757-e945d38000bd");
+        assert!(matches!(&results[2], SearchResult::DataSource(data_source)
+        if data_source.parent == Parent::DatabaseId {
+            database_id: "248104cd-477e-80fd-b757-e945d38000bd".to_owned()
+        }));
+        assert!(matches!(results[3], SearchResult::Unknown(_)));
+        assert_eq!(results[3].id(), "1a2b");
+        assert_eq!(json!(results[3]), json!({ "object": "form", "id": "1a2b" }));
+        assert_eq!(
+            SearchFilter::Databases.to_json(ApiVersion::V2022_06_28),
+            json!({ "property": "object", "value": "database" })
+        );
+        assert_eq!(
+            SearchFilter::Databases.to_json(ApiVersion::V2025_09_03),
+            json!({ "property": "object", "value": "data_source" })
+        );
+    }
+
+    #[cfg(feature = "request")]
+    #[tokio::test]
+    async fn queries_go_to_the_first_data_source_since_2025_09_03() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+
+        let databases = Client::new()
+            .api_key("secret")
+            .notion_version(ApiVersion::V2025_09_03)
+            .custom_request(move |request| {
+                let recorded = recorded.clone();
+
+                Box::pin(async move {
+                    let request = request.try_clone().unwrap().build()?;
+                    recorded.lock().unwrap().push(format!(
+                        "{} {}",
+                        request.method(),
+                        request.url().path()
+                    ));
+
+                    let body = if request.method() == reqwest::Method::GET {
+                        json!({
+                            "object": "database",
+                            "id": "248104cd-477e-80fd-b757-e945d38000bd",
+                            "title": [],
+                            "description": [],
+                            "url": "https://www.notion.so/248104cd477e80fdb757e945d38000bd",
+                            "parent": { "type": "workspace", "workspace": true },
+                            "created_time": "2025-08-07T10:11:07.504Z",
+                            "last_edited_time": "2025-08-10T15:53:11.386Z",
+                            "last_edited_by": { "object": "user", "id": "ee5f0f84-409a-440f-983a-a5315961c6e4" },
+                            "icon": null,
+                            "cover": null,
+                            "in_trash": false,
+                            "is_inline": false,
+                            "data_sources": [{ "id": "248104cd-477e-80af-bc30-000bd28de8f9", "name": "Tasks" }]
+                        })
+                    } else {
+                        json!({ "object": "list", "results": [], "next_cursor": null, "has_more": false })
+                    };
+
+                    Ok(reqwest::Response::from(http::Response::new(body.to_string())))
+                })
+            })
+            .build()
+            .unwrap()
+            .databases;
+
+        for _ in 0..2 {
+            databases
+                .query(DatabaseQueryOptions {
+                    database_id: DatabaseId::new_unchecked("248104cd477e80fdb757e945d38000bd"),
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(
+            *requests.lock().unwrap(),
+            [
+                "GET /v1/databases/248104cd477e80fdb757e945d38000bd",
+                "POST /v1/data_sources/248104cd-477e-80af-bc30-000bd28de8f9/query",
+                "POST /v1/data_sources/248104cd-477e-80af-bc30-000bd28de8f9/query",
+            ]
+        );
+    }
+
+    fn page_with_properties(properties: Value) -> Value {
+        json!({
+            "id": "59833787-2cf9-4fdf-8782-e53db20768a5",
+            "created_by": { "id": "ee5f0f84-409a-440f-983a-a5315961c6e4" },
+            "url": "https://www.notion.so/59833787-2cf9-4fdf-8782-e53db20768a5",
+            "parent": { "type": "workspace", "workspace": true },
+            "created_time": "2022-03-01T19:05:00.000Z",
+            "last_edited_time": "2022-07-06T20:25:00.000Z",
+            "cover": null,
+            "icon": null,
+            "properties": properties,
+            "archived": false
+        })
+    }
+
+    #[test]
+    fn lenient_properties_collect_warnings() {
+        let page = serde_json::from_value::<Page>(page_with_properties(json!({
+            "Name": { "id": "title", "type": "title", "title": {} },
+            "Done": { "id": "a%3Bc", "type": "checkbox", "checkbox": {} },
+            "Size": { "id": "b%3Bd", "type": "number", "number": "big" },
+        })))
+        .unwrap();
+
+        let mut paths = page
+            .warnings
+            .iter()
+            .map(|warning| warning.path.as_str())
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        assert_eq!(
+            paths,
+            vec![
+                "properties.Done.checkbox",
+                "properties.Done.checkbox",
+                "properties.Name.title",
+                "properties.Name.title",
+                "properties.Size",
+            ]
+        );
+        assert!(matches!(
+            page.properties.get("Done"),
+            Some(Property::Checkbox {
+                checkbox: false,
+                ..
+            })
+        ));
+        assert!(matches!(
+            page.properties.get("Size"),
+            Some(Property::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn warnings_stay_with_their_object() {
+        let mut broken = page_with_properties(json!({
+            "Size": { "id": "b%3Bd", "type": "number", "number": "big" },
+        }));
+        // Read after the properties, which have left a warning behind by then
+        broken["url"] = json!(42);
+        let database = json!({
+            "id": "248104cd-477e-80fd-b757-e945d38000bd",
+            "title": [],
+            "description": [],
+            "url": "https://www.notion.so/248104cd477e80fdb757e945d38000bd",
+            "parent": { "type": "workspace", "workspace": true },
+            "created_time": "2025-08-07T10:11:07.504Z",
+            "last_edited_time": "2025-08-10T15:53:11.386Z",
+            "last_edited_by": { "object": "user", "id": "ee5f0f84-409a-440f-983a-a5315961c6e4" },
+            "icon": null,
+            "cover": null,
+            "is_inline": false
+        });
+
+        assert!(serde_json::from_value::<Page>(broken).is_err());
+        assert_eq!(
+            serde_json::from_value::<Database>(database)
+                .unwrap()
+                .warnings,
+            vec![]
+        );
+    }
+
+    #[test]
+    fn settings_are_restored_after_panics() {
+        let panicked = std::panic::catch_unwind(|| {
+            with_deserialization_mode(DeserializationMode::Strict, || panic!("callback"))
+        });
+
+        assert!(panicked.is_err());
+        assert_eq!(deserialization_mode(), DeserializationMode::Lenient);
+    }
+
+    #[test]
+    fn strict_properties_fail_with_path() {
+        let error = with_deserialization_mode(DeserializationMode::Strict, || {
+            serde_json::from_value::<Page>(page_with_properties(json!({
+                "Done": { "id": "a%3Bc", "type": "checkbox", "checkbox": null },
+            })))
+        })
+        .unwrap_err();
+
+        assert!(error
+            .to_string()
+            .starts_with("properties.Done.checkbox: missing checkbox value"));
+    }
+}
+
+pub struct DatabaseOptions {
+    pub database_id: DatabaseId,
 }
 
 #[derive(Debug, Default)]
-pub struct DatabaseQueryOptions<'a> {
-    pub database_id: &'a str,
+pub struct DatabaseQueryOptions {
+    pub database_id: DatabaseId,
     // TODO: Implement spec for filter?
     pub filter: Option<Value>,
     pub sorts: Option<Value>,
@@ -422,7 +2292,8 @@ pub struct DatabaseQueryOptions<'a> {
 #[derive(Clone)]
 pub struct Users {
     http_client: Arc<reqwest::Client>,
-    request_handler: Arc<Callback>,
+    request_handler: Arc<RequestHandler>,
+    deserialization_mode: DeserializationMode,
 }
 
 impl Users {
@@ -431,16 +2302,360 @@ impl Users {
 
         let mut request = self.http_client.get(&url);
 
-        let response = (self.request_handler)(&mut request).await?;
+        let response = (self.request_handler)(&mut request).await?;
+
+        match response.error_for_status_ref() {
+            Ok(_) => try_to_parse_response(response, self.deserialization_mode).await,
+            Err(error) => {
+                let body = response.json::<Value>().await?;
+                Err(Error::Http(error, Some(body)))
+            }
+        }
+    }
+
+    pub async fn retrieve(&self, options: UserOptions) -> Result<User> {
+        let url = format!(
+            "https://api.notion.com/v1/users/{user_id}",
+            user_id = options.user_id
+        );
+
+        let mut request = self.http_client.get(url);
+
+        let response = (self.request_handler)(&mut request).await?;
+
+        match response.error_for_status_ref() {
+            Ok(_) => try_to_parse_response(response, self.deserialization_mode).await,
+            Err(error) => {
+                let body = response.json::<Value>().await?;
+                Err(Error::Http(error, Some(body)))
+            }
+        }
+    }
+
+    /// Retrieves the bot user of the API key.
+    pub async fn me(&self) -> Result<User> {
+        self.retrieve(UserOptions {
+            user_id: "me".into(),
+        })
+        .await
+    }
+}
+
+pub struct UserOptions {
+    pub user_id: NotionId,
+}
+
+#[derive(Clone)]
+pub struct Comments {
+    http_client: Arc<reqwest::Client>,
+    request_handler: Arc<RequestHandler>,
+    deserialization_mode: DeserializationMode,
+}
+
+impl Comments {
+    /// Lists all unresolved comments on a page or block, fetching every page of results.
+    pub async fn list(&self, options: CommentListOptions) -> Result<Vec<Comment>> {
+        let mut comments = Vec::new();
+        let mut cursor = None;
+
+        loop {
+            let mut request = self
+                .http_client
+                .get("https://api.notion.com/v1/comments")
+                .query(&[
+                    ("block_id", options.block_id.as_str()),
+                    ("page_size", "100"),
+                ]);
+            if let Some(cursor) = &cursor {
+                request = request.query(&[("start_cursor", cursor)]);
+            }
+
+            let response = (self.request_handler)(&mut request).await?;
+
+            let page = match response.error_for_status_ref() {
+                Ok(_) => {
+                    try_to_parse_response::<QueryResponse<Comment>>(
+                        response,
+                        self.deserialization_mode,
+                    )
+                    .await?
+                }
+                Err(error) => {
+                    let body = response.json::<Value>().await?;
+                    return Err(Error::Http(error, Some(body)));
+                }
+            };
+
+            comments.extend(page.results);
+            match page.next_cursor {
+                Some(next_cursor) if page.has_more => cursor = Some(next_cursor),
+                _ => return Ok(comments),
+            }
+        }
+    }
+
+    /// Starts a new discussion on a page, or replies to an existing one.
+    pub async fn create(&self, options: CommentCreateOptions) -> Result<Comment> {
+        let rich_text = options
+            .rich_text
+            .iter()
+            .map(|rich_text| {
+                let mut value = serde_json::to_value(rich_text)?;
+                if let Some(object) = value.as_object_mut() {
+                    // Read only fields
+                    object.remove("plain_text");
+                    object.remove("href");
+                }
+                strip_for_append(&mut value, false);
+
+                Ok(value)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let body = match options.target {
+            CommentTarget::Page(page_id) => json!({
+                "parent": { "page_id": page_id },
+                "rich_text": rich_text,
+            }),
+            CommentTarget::Discussion(discussion_id) => json!({
+                "discussion_id": discussion_id,
+                "rich_text": rich_text,
+            }),
+        };
+
+        let mut request = self
+            .http_client
+            .post("https://api.notion.com/v1/comments")
+            .json(&body);
+
+        let response = (self.request_handler)(&mut request).await?;
+
+        match response.error_for_status_ref() {
+            Ok(_) => try_to_parse_response(response, self.deserialization_mode).await,
+            Err(error) => {
+                let body = response.json::<Value>().await?;
+                Err(Error::Http(error, Some(body)))
+            }
+        }
+    }
+}
+
+pub struct CommentListOptions {
+    pub block_id: BlockId,
+}
+
+pub enum CommentTarget {
+    /// Starts a new discussion on the page with this id.
+    Page(PageId),
+    /// Replies to the discussion with this id.
+    Discussion(NotionId),
+}
+
+pub struct CommentCreateOptions {
+    pub target: CommentTarget,
+    pub rich_text: Vec<RichText>,
+}
+
+/// Notion accepts up to 20 MB in a single part, larger files are sent in parts of 5 to 20 MB.
+const MAX_SINGLE_PART_SIZE: usize = 20 * 1024 * 1024;
+const PART_SIZE: usize = 10 * 1024 * 1024;
+
+#[derive(Clone)]
+pub struct FileUploads {
+    http_client: Arc<reqwest::Client>,
+    request_handler: Arc<RequestHandler>,
+    deserialization_mode: DeserializationMode,
+}
+
+impl FileUploads {
+    /// Uploads `content` in a single part, or in parts if it is too large for one, and returns
+    /// the upload to attach with `FileUpload::file`.
+    pub async fn upload<'a>(&self, options: FileUploadContentOptions<'a>) -> Result<FileUpload> {
+        let number_of_parts = if options.content.len() > MAX_SINGLE_PART_SIZE {
+            Some(options.content.len().div_ceil(PART_SIZE))
+        } else {
+            None
+        };
+
+        let upload = self
+            .create(FileUploadCreateOptions {
+                filename: options.filename,
+                content_type: options.content_type,
+                number_of_parts,
+            })
+            .await?;
+
+        if number_of_parts.is_none() {
+            return self
+                .send(FileUploadSendOptions {
+                    file_upload_id: &upload.id,
+                    filename: options.filename,
+                    content_type: options.content_type,
+                    content: options.content,
+                    part_number: None,
+                })
+                .await;
+        }
+
+        for (index, part) in options.content.chunks(PART_SIZE).enumerate() {
+            self.send(FileUploadSendOptions {
+                file_upload_id: &upload.id,
+                filename: options.filename,
+                content_type: options.content_type,
+                content: part,
+                part_number: Some(index + 1),
+            })
+            .await?;
+        }
+
+        self.complete(FileUploadOptions {
+            file_upload_id: &upload.id,
+        })
+        .await
+    }
+
+    pub async fn create<'a>(&self, options: FileUploadCreateOptions<'a>) -> Result<FileUpload> {
+        let mut body = json!({
+            "filename": options.filename,
+            "content_type": options.content_type,
+            "mode": "single_part",
+        });
+        if let Some(number_of_parts) = options.number_of_parts {
+            body["mode"] = json!("multi_part");
+            body["number_of_parts"] = json!(number_of_parts);
+        }
+
+        let request = self
+            .http_client
+            .post("https://api.notion.com/v1/file_uploads")
+            .json(&body);
+
+        self.execute(request).await
+    }
+
+    /// Sends the content of a single-part upload, or one part of a multi-part upload.
+    pub async fn send<'a>(&self, options: FileUploadSendOptions<'a>) -> Result<FileUpload> {
+        let url = format!(
+            "https://api.notion.com/v1/file_uploads/{file_upload_id}/send",
+            file_upload_id = options.file_upload_id
+        );
+        let (boundary, body) = multipart_body(&options)?;
+
+        let request = self
+            .http_client
+            .post(url)
+            .header(
+                "Content-Type",
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(body);
+
+        self.execute(request).await
+    }
+
+    /// Finishes a multi-part upload once all parts are sent.
+    pub async fn complete<'a>(&self, options: FileUploadOptions<'a>) -> Result<FileUpload> {
+        let url = format!(
+            "https://api.notion.com/v1/file_uploads/{file_upload_id}/complete",
+            file_upload_id = options.file_upload_id
+        );
+
+        self.execute(self.http_client.post(url)).await
+    }
+
+    pub async fn retrieve<'a>(&self, options: FileUploadOptions<'a>) -> Result<FileUpload> {
+        let url = format!(
+            "https://api.notion.com/v1/file_uploads/{file_upload_id}",
+            file_upload_id = options.file_upload_id
+        );
+
+        self.execute(self.http_client.get(url)).await
+    }
+
+    async fn execute(&self, mut request: reqwest::RequestBuilder) -> Result<FileUpload> {
+        let response = (self.request_handler)(&mut request).await?;
+
+        match response.error_for_status_ref() {
+            Ok(_) => try_to_parse_response(response, self.deserialization_mode).await,
+            Err(error) => {
+                let body = response.json::<Value>().await?;
+                Err(Error::Http(error, Some(body)))
+            }
+        }
+    }
+}
+
+/// Encodes the form of `FileUploads::send` by hand, as reqwest's multipart forms are streamed
+/// and can't be cloned for the request handler.
+fn multipart_body(options: &FileUploadSendOptions) -> Result<(String, Vec<u8>)> {
+    let content_type = options
+        .content_type
+        .parse::<mime::Mime>()
+        .map_err(|_| Error::InvalidContentType(options.content_type.to_owned()))?;
+    // Escaped the way browsers do, which keeps the name within its quotes and on its line
+    let filename = options
+        .filename
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A");
+
+    let mut boundary = format!("notion-client-{}", options.file_upload_id);
+    while options
+        .content
+        .windows(boundary.len())
+        .any(|window| window == boundary.as_bytes())
+    {
+        boundary.push('-');
+    }
+
+    let mut body = Vec::with_capacity(options.content.len() + 512);
+    if let Some(part_number) = options.part_number {
+        body.extend_from_slice(
+            format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"part_number\"\r\n\r\n{part_number}\r\n"
+            )
+            .as_bytes(),
+        );
+    }
+    body.extend_from_slice(
+        format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{filename}\"\r\nContent-Type: {content_type}\r\n\r\n"
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(options.content);
+    body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+
+    Ok((boundary, body))
+}
+
+pub struct FileUploadOptions<'a> {
+    pub file_upload_id: &'a str,
+}
+
+pub struct FileUploadContentOptions<'a> {
+    pub filename: &'a str,
+    /// The MIME type, e.g. `image/png`.
+    pub content_type: &'a str,
+    pub content: &'a [u8],
+}
+
+pub struct FileUploadCreateOptions<'a> {
+    pub filename: &'a str,
+    pub content_type: &'a str,
+    /// The number of parts of a multi-part upload, `None` to send the content at once.
+    pub number_of_parts: Option<usize>,
+}
 
-        match response.error_for_status_ref() {
-            Ok(_) => Ok(response.json().await?),
-            Err(error) => {
-                let body = response.json::<Value>().await?;
-                Err(Error::Http(error, Some(body)))
-            }
-        }
-    }
+pub struct FileUploadSendOptions<'a> {
+    pub file_upload_id: &'a str,
+    /// The name Notion shows for the file.
+    pub filename: &'a str,
+    /// The MIME type, e.g. `image/png`.
+    pub content_type: &'a str,
+    pub content: &'a [u8],
+    /// The 1-based number of the part of a multi-part upload.
+    pub part_number: Option<usize>,
 }
 
 // Start of normal entities
@@ -454,11 +2669,201 @@ pub struct Block {
     pub created_by: PartialUser,
     pub last_edited_by: PartialUser,
     pub has_children: bool,
+    /// Not returned since `ApiVersion::V2025_09_03`, see `in_trash`.
+    #[serde(default)]
     pub archived: bool,
+    /// Only returned since `ApiVersion::V2025_09_03`.
+    #[serde(default)]
+    pub in_trash: Option<bool>,
     #[serde(flatten)]
     pub block: BlockType,
 }
 
+impl Block {
+    /// Wraps a block that doesn't exist in Notion yet, e.g. to nest it in the `children` of a
+    /// `BlockType` that is about to be appended.
+    pub fn new(block: BlockType) -> Block {
+        Block {
+            id: String::new(),
+            parent: Parent::Workspace,
+            created_time: DateValue::DateTime(DateTime::default()),
+            last_edited_time: DateValue::DateTime(DateTime::default()),
+            created_by: PartialUser { id: String::new() },
+            last_edited_by: PartialUser { id: String::new() },
+            has_children: block
+                .children()
+                .is_some_and(|children| !children.is_empty()),
+            archived: false,
+            in_trash: None,
+            block,
+        }
+    }
+}
+
+impl BlockType {
+    /// The file of an image, video, PDF or file block.
+    pub fn file(&self) -> Option<&File> {
+        match self {
+            BlockType::File { file }
+            | BlockType::Image { image: file }
+            | BlockType::Pdf { pdf: file }
+            | BlockType::Video { video: file } => Some(file),
+            _ => None,
+        }
+    }
+
+    pub fn children(&self) -> Option<&Vec<Block>> {
+        use BlockType::*;
+
+        match self {
+            Paragraph {
+                paragraph: crate::Paragraph { children, .. },
+            }
+            | BulletedListItem {
+                bulleted_list_item: ListItem { children, .. },
+            }
+            | NumberedListItem {
+                numbered_list_item: ListItem { children, .. },
+            }
+            | ToDo {
+                to_do: ToDoItem { children, .. },
+            }
+            | Toggle {
+                toggle: crate::Toggle { children, .. },
+            }
+            | Quote {
+                quote: crate::Quote { children, .. },
+            }
+            | Callout {
+                callout: crate::Callout { children, .. },
+            }
+            | Heading1 {
+                heading: Heading { children, .. },
+            }
+            | Heading2 {
+                heading: Heading { children, .. },
+            }
+            | Heading3 {
+                heading: Heading { children, .. },
+            }
+            | Table {
+                table: crate::Table { children, .. },
+            }
+            | Column {
+                column: crate::Column { children },
+            }
+            | ColumnList {
+                column_list: crate::ColumnList { children },
+            }
+            | ChildPage {
+                child_page: crate::ChildPage { children, .. },
+            }
+            | ChildDatabase {
+                child_database: crate::ChildDatabase { children, .. },
+            } => children.as_ref(),
+
+            _ => None,
+        }
+    }
+
+    /// Returns the `children` field of blocks that can have children.
+    pub fn children_mut(&mut self) -> Option<&mut Option<Vec<Block>>> {
+        use BlockType::*;
+
+        match self {
+            Paragraph {
+                paragraph: crate::Paragraph { children, .. },
+            }
+            | BulletedListItem {
+                bulleted_list_item: ListItem { children, .. },
+            }
+            | NumberedListItem {
+                numbered_list_item: ListItem { children, .. },
+            }
+            | ToDo {
+                to_do: ToDoItem { children, .. },
+            }
+            | Toggle {
+                toggle: crate::Toggle { children, .. },
+            }
+            | Quote {
+                quote: crate::Quote { children, .. },
+            }
+            | Callout {
+                callout: crate::Callout { children, .. },
+            }
+            | Heading1 {
+                heading: Heading { children, .. },
+            }
+            | Heading2 {
+                heading: Heading { children, .. },
+            }
+            | Heading3 {
+                heading: Heading { children, .. },
+            }
+            | Table {
+                table: crate::Table { children, .. },
+            }
+            | Column {
+                column: crate::Column { children },
+            }
+            | ColumnList {
+                column_list: crate::ColumnList { children },
+            }
+            | ChildPage {
+                child_page: crate::ChildPage { children, .. },
+            }
+            | ChildDatabase {
+                child_database: crate::ChildDatabase { children, .. },
+            } => Some(children),
+
+            _ => None,
+        }
+    }
+}
+
+/// Serializes `block` the way the append children endpoint expects it: without `null`s, and with
+/// nested children stripped of everything but their type and content.
+pub fn append_json(block: &BlockType) -> Value {
+    let mut value = serde_json::to_value(block).unwrap_or(Value::Null);
+    strip_for_append(&mut value, true);
+
+    value
+}
+
+fn strip_for_append(value: &mut Value, is_block: bool) {
+    match value {
+        Value::Object(object) => {
+            object.retain(|_, value| !value.is_null());
+
+            if is_block {
+                let kind = object
+                    .get("type")
+                    .and_then(Value::as_str)
+                    .map(str::to_owned)
+                    .unwrap_or_default();
+
+                object.retain(|key, _| key == "type" || key == &kind);
+                // Blocks without content, like dividers, still need an empty object
+                object.entry(kind).or_insert_with(|| json!({}));
+            }
+
+            for (key, value) in object.iter_mut() {
+                match (key.as_str(), value) {
+                    ("children", Value::Array(children)) => children
+                        .iter_mut()
+                        .for_each(|child| strip_for_append(child, true)),
+                    (_, value) => strip_for_append(value, false),
+                }
+            }
+        }
+        Value::Array(values) => values
+            .iter_mut()
+            .for_each(|value| strip_for_append(value, false)),
+        _ => {}
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(tag = "type")]
 #[serde(rename_all = "snake_case")]
@@ -485,8 +2890,12 @@ pub enum BlockType {
     Code {
         code: Code,
     },
-    Column,
-    ColumnList,
+    Column {
+        column: Column,
+    },
+    ColumnList {
+        column_list: ColumnList,
+    },
     Divider,
     Embed {
         embed: Embed,
@@ -497,13 +2906,19 @@ pub enum BlockType {
     File {
         file: File,
     },
+    #[serde(rename = "heading_1")]
     Heading1 {
+        #[serde(rename = "heading_1")]
         heading: Heading,
     },
+    #[serde(rename = "heading_2")]
     Heading2 {
+        #[serde(rename = "heading_2")]
         heading: Heading,
     },
+    #[serde(rename = "heading_3")]
     Heading3 {
+        #[serde(rename = "heading_3")]
         heading: Heading,
     },
     Image {
@@ -523,14 +2938,20 @@ pub enum BlockType {
         quote: Quote,
     },
     SyncedBlock,
-    Table,
+    Table {
+        table: Table,
+    },
     TableOfContents,
-    TableRow,
+    TableRow {
+        table_row: TableRow,
+    },
     Template,
     ToDo {
         to_do: ToDoItem,
     },
-    Toggle,
+    Toggle {
+        toggle: Toggle,
+    },
     Video {
         video: File,
     },
@@ -556,6 +2977,7 @@ pub struct Heading {
     pub color: Color,
     pub rich_text: Vec<RichText>,
     pub is_toggleable: bool,
+    pub children: Option<Vec<Block>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -571,7 +2993,7 @@ pub struct FileBlock {
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct ColumnList {
-    pub children: Option<Vec<Column>>,
+    pub children: Option<Vec<Block>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -579,6 +3001,26 @@ pub struct Column {
     pub children: Option<Vec<Block>>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Table {
+    pub table_width: u32,
+    pub has_column_header: bool,
+    pub has_row_header: bool,
+    pub children: Option<Vec<Block>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TableRow {
+    pub cells: Vec<Vec<RichText>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Toggle {
+    pub color: Color,
+    pub rich_text: Vec<RichText>,
+    pub children: Option<Vec<Block>>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Callout {
     pub icon: Option<Icon>,
@@ -732,19 +3174,59 @@ pub enum CodeLanguage {
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct ChildPage {
     pub title: String,
+    /// The content of the page, only filled in by `Blocks::fetch_tree`.
+    pub children: Option<Vec<Block>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct ChildDatabase {
     pub title: String,
+    /// The rows of the database as `ChildPage` blocks, only filled in by `Blocks::fetch_tree`.
+    pub children: Option<Vec<Block>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type")]
+#[serde(rename_all = "snake_case")]
+pub enum User {
+    Person {
+        id: String,
+        name: Option<String>,
+        avatar_url: Option<String>,
+        person: Person,
+    },
+    Bot {
+        id: String,
+        name: Option<String>,
+        avatar_url: Option<String>,
+        bot: Bot,
+    },
+}
+
+impl User {
+    pub fn id(&self) -> &str {
+        match self {
+            User::Person { id, .. } | User::Bot { id, .. } => id,
+        }
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            User::Person { name, .. } | User::Bot { name, .. } => name.as_deref(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
-pub struct User {
+pub struct Comment {
     pub id: String,
-    pub name: Option<String>,
-    pub person: Option<Person>,
-    pub avatar_url: Option<String>,
+    /// The page, or the block for comments on inline discussions.
+    pub parent: Parent,
+    pub discussion_id: String,
+    pub created_time: DateValue,
+    pub last_edited_time: DateValue,
+    pub created_by: PartialUser,
+    pub rich_text: Vec<RichText>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -754,16 +3236,35 @@ pub struct Workspace {
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Person {
-    pub email: String,
+    /// Only present if the integration has access to user email addresses.
+    pub email: Option<String>,
+}
+
+/// The `bot` of bot users; empty for bots other than the one of the API key.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct Bot {
+    pub owner: Option<BotOwner>,
+    pub workspace_name: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type")]
+#[serde(rename_all = "snake_case")]
+pub enum BotOwner {
+    /// Internal integrations are owned by their workspace.
+    Workspace(Workspace),
+    /// Public integrations are owned by the user who authorized them.
+    User { user: PartialUser },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(remote = "Self")]
 pub struct Database {
     pub id: String,
     pub title: Vec<RichText>,
     pub description: Vec<RichText>,
 
-    #[serde(deserialize_with = "deserialize_database_properties")]
+    #[serde(default, deserialize_with = "deserialize_database_properties")]
     pub properties: HashMap<String, DatabaseProperty>,
     pub url: String,
 
@@ -773,8 +3274,52 @@ pub struct Database {
     pub last_edited_by: PartialUser,
     pub icon: Option<Icon>,
     pub cover: Option<File>,
+    /// Not returned since `ApiVersion::V2025_09_03`, see `in_trash`.
+    #[serde(default)]
     pub archived: bool,
+    /// Only returned since `ApiVersion::V2025_09_03`.
+    #[serde(default)]
+    pub in_trash: Option<bool>,
     pub is_inline: bool,
+    /// Since `ApiVersion::V2025_09_03`, the properties and rows of a database live in its data
+    /// sources, and `properties` is empty. Not returned before.
+    #[serde(default)]
+    pub data_sources: Option<Vec<DataSourceReference>>,
+
+    #[serde(skip)]
+    pub warnings: Vec<PropertyWarning>,
+}
+
+/// A table of a database, which holds its properties and rows since `ApiVersion::V2025_09_03`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(remote = "Self")]
+pub struct DataSource {
+    pub id: String,
+    #[serde(default)]
+    pub title: Vec<RichText>,
+    #[serde(default)]
+    pub description: Vec<RichText>,
+
+    #[serde(default, deserialize_with = "deserialize_database_properties")]
+    pub properties: HashMap<String, DatabaseProperty>,
+
+    /// The database the data source belongs to.
+    pub parent: Parent,
+    pub created_time: DateValue,
+    pub last_edited_time: DateValue,
+    #[serde(default)]
+    pub icon: Option<Icon>,
+    #[serde(default)]
+    pub in_trash: bool,
+
+    #[serde(skip)]
+    pub warnings: Vec<PropertyWarning>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DataSourceReference {
+    pub id: String,
+    pub name: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -861,7 +3406,7 @@ pub enum DatabaseProperty {
     Status {
         id: String,
         name: String,
-        // TODO: Implement Status
+        status: DatabaseSelectOptions,
     },
     Title {
         id: String,
@@ -939,25 +3484,40 @@ fn deserialize_database_properties<'de, D>(
 where
     D: Deserializer<'de>,
 {
-    Ok(Value::deserialize(deserializer)?
+    let mode = deserialization_mode();
+    let mut warnings = Vec::new();
+
+    let properties = Value::deserialize(deserializer)?
         .as_object_mut()
         .ok_or(Error::UnexpectedType)
         .map_err(D::Error::custom)?
-        .into_iter()
+        .iter()
         .map(|(key, value)| {
-            (
-                key.to_owned(),
-
-                serde_json::from_value::<DatabaseProperty>(value.to_owned()).unwrap_or_else(|error| {
+            match serde_json::from_value::<DatabaseProperty>(value.to_owned()) {
+                Ok(property) => Ok((key.to_owned(), property)),
+                Err(error) if mode == DeserializationMode::Strict => Err(
+                    D::Error::custom(format!("properties.{key}: {error}")),
+                ),
+                Err(error) => {
                     log::warn!(
                         "Could not parse value because of error, defaulting to DatabaseProperty::Unsupported:\n= ERROR:\n{error:#?}\n= JSON:\n{:#?}\n---",
                         serde_json::to_string_pretty(&value).unwrap()
                     );
-                    DatabaseProperty::Unsupported(value.to_owned())
-                }),
-            )
+                    warnings.push(PropertyWarning {
+                        property: key.to_owned(),
+                        path: format!("properties.{key}"),
+                        patch: PropertyPatch::Unsupported(error.to_string()),
+                    });
+
+                    Ok((key.to_owned(), DatabaseProperty::Unsupported(value.to_owned())))
+                }
+            }
         })
-        .collect::<HashMap<String, DatabaseProperty>>())
+        .collect::<std::result::Result<HashMap<String, DatabaseProperty>, D::Error>>()?;
+
+    PROPERTY_WARNINGS.with(|buffer| *buffer.borrow_mut() = warnings);
+
+    Ok(properties)
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -983,9 +3543,12 @@ pub struct QueryResponse<T> {
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(remote = "Self")]
 pub struct Page {
     pub id: String,
     pub created_by: PartialUser,
+    #[serde(default)]
+    pub last_edited_by: Option<PartialUser>,
     pub url: String,
     pub parent: Parent,
 
@@ -998,7 +3561,15 @@ pub struct Page {
     #[serde(deserialize_with = "deserialize_properties")]
     pub properties: HashMap<String, Property>,
 
+    /// Not returned since `ApiVersion::V2025_09_03`, see `in_trash`.
+    #[serde(default)]
     pub archived: bool,
+    /// Only returned since `ApiVersion::V2025_09_03`.
+    #[serde(default)]
+    pub in_trash: Option<bool>,
+
+    #[serde(skip)]
+    pub warnings: Vec<PropertyWarning>,
 }
 
 impl Page {
@@ -1022,6 +3593,42 @@ impl Page {
     }
 }
 
+/// A property value that had to be patched up or skipped while deserializing in
+/// `DeserializationMode::Lenient`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyWarning {
+    pub property: String,
+    pub path: String,
+    pub patch: PropertyPatch,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyPatch {
+    EmptyObjectToNull,
+    DefaultedFormulaType,
+    DefaultedTitle,
+    DefaultedCheckbox,
+    Unsupported(String),
+}
+
+impl std::fmt::Display for PropertyPatch {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PropertyPatch::EmptyObjectToNull => write!(formatter, "empty object instead of null"),
+            PropertyPatch::DefaultedFormulaType => {
+                write!(formatter, "missing formula type, defaulted to \"string\"")
+            }
+            PropertyPatch::DefaultedTitle => {
+                write!(formatter, "missing title, defaulted to \"Unknown title\"")
+            }
+            PropertyPatch::DefaultedCheckbox => {
+                write!(formatter, "missing checkbox value, defaulted to false")
+            }
+            PropertyPatch::Unsupported(error) => write!(formatter, "unsupported value: {error}"),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(tag = "type")]
 #[serde(rename_all = "snake_case")]
@@ -1075,13 +3682,17 @@ pub enum Property {
     },
     People {
         id: String,
+        people: Vec<PartialUser>,
     },
     PhoneNumber {
         id: String,
+        phone_number: Option<String>,
     },
     Relation {
         id: String,
-        // relation: Vec<Relation>,
+        relation: Vec<PartialPage>,
+        #[serde(default)]
+        has_more: bool,
     },
     Rollup {
         id: String,
@@ -1092,7 +3703,8 @@ pub enum Property {
     },
     Status {
         id: String,
-    }, // TODO: Implement Status
+        status: Option<SelectOption>,
+    },
     Title {
         id: String,
         title: Vec<RichText>,
@@ -1150,76 +3762,128 @@ fn deserialize_properties<'de, D>(
 where
     D: Deserializer<'de>,
 {
-    Ok(Value::deserialize(deserializer)?
+    let mode = deserialization_mode();
+    let mut warnings = Vec::new();
+
+    let properties = Value::deserialize(deserializer)?
         .as_object_mut()
         .ok_or(Error::UnexpectedType)
         .map_err(D::Error::custom)?
-        .into_iter()
+        .iter_mut()
         .map(|(key, value)| {
-            if let Value::Object(ref mut object) = value {
-                // Notion sometimes sends an empty object when it means "null", so we gotta do it's homework
-                for value in object.values_mut() {
-                    if value == &mut json!({}) {
-                        *value = Value::Null
-                    }
-                }
-
-                // Correcting missing values
-                for (key, value) in object.iter_mut() {
-                    match key.as_ref() {
-                        // Notion forgets to set the formula type, so we're doing it's homework
-                        "formula" => {
-                            if let Value::Object(object) = value {
-                                if let None = object.get("type") {
-                                    object.insert("type".to_owned(), json!("string"));
-                                }
-                            }
-                        }
-                        // Notion sometimes just sets title to an empty object? Guess we have to set it to something?
-                        "title" => {
-                            if let Value::Null = value {
-                                *value = json!([{
-                                  "type": "text",
-                                  "text": {
-                                    "content": "Unknown title",
-                                    "link": null
-                                  },
-                                  "annotations": {
-                                    "bold": false,
-                                    "italic": false,
-                                    "strikethrough": false,
-                                    "underline": false,
-                                    "code": false,
-                                    "color": "default"
-                                  },
-                                  "plain_text": "Unknown title",
-                                  "href": null
-                                }])
-                            }
-                        }
-                        // Notion sometimes forget to set a value for a checkbox??
-                        "checkbox" => {
-                            if let Value::Null = value {
-                                *value = json!(false)
-                            }
-                        }
-                        _ => {}
-                    };
-                }
-            }
-
-            (
-                key.to_owned(),
-                serde_json::from_value::<Property>(value.to_owned()).unwrap_or_else(|error| {
+            patch_property(key, value, mode, &mut warnings)?;
+
+            match serde_json::from_value::<Property>(value.to_owned()) {
+                Ok(property) => Ok((key.to_owned(), property)),
+                Err(error) if mode == DeserializationMode::Strict => Err(
+                    D::Error::custom(format!("properties.{key}: {error}")),
+                ),
+                Err(error) => {
                     log::warn!(
                         "Could not parse value because of error, defaulting to Property::Unsupported:\n= ERROR:\n{error:#?}\n= JSON:\n{}\n---",
                         serde_json::to_string_pretty(&value).unwrap()
                     );
-                    Property::Unsupported(value.to_owned())
-                }),
-            )
+                    warnings.push(PropertyWarning {
+                        property: key.to_owned(),
+                        path: format!("properties.{key}"),
+                        patch: PropertyPatch::Unsupported(error.to_string()),
+                    });
+
+                    Ok((key.to_owned(), Property::Unsupported(value.to_owned())))
+                }
+            }
         })
-        .collect::<HashMap<String, Property>>())
+        .collect::<std::result::Result<HashMap<String, Property>, D::Error>>()?;
+
+    PROPERTY_WARNINGS.with(|buffer| *buffer.borrow_mut() = warnings);
+
+    Ok(properties)
+}
+
+/// Does Notion's homework on a single property value, either patching it up (lenient) or
+/// refusing it with the path of the offending key (strict).
+fn patch_property<E: SerdeError>(
+    name: &str,
+    value: &mut Value,
+    mode: DeserializationMode,
+    warnings: &mut Vec<PropertyWarning>,
+) -> std::result::Result<(), E> {
+    let Value::Object(ref mut object) = value else {
+        return Ok(());
+    };
+
+    let mut patch = |key: &str, patch: PropertyPatch| {
+        let path = format!("properties.{name}.{key}");
+
+        match mode {
+            DeserializationMode::Strict => Err(E::custom(format!("{path}: {patch}"))),
+            DeserializationMode::Lenient => {
+                warnings.push(PropertyWarning {
+                    property: name.to_owned(),
+                    path,
+                    patch,
+                });
+
+                Ok(())
+            }
+        }
+    };
+
+    // Notion sometimes sends an empty object when it means "null", so we gotta do it's homework
+    for (key, value) in object.iter_mut() {
+        if value == &json!({}) {
+            patch(key, PropertyPatch::EmptyObjectToNull)?;
+            *value = Value::Null
+        }
+    }
+
+    // Correcting missing values
+    for (key, value) in object.iter_mut() {
+        match key.as_ref() {
+            // Notion forgets to set the formula type, so we're doing it's homework
+            "formula" => {
+                if let Value::Object(object) = value {
+                    if object.get("type").is_none() {
+                        patch("formula.type", PropertyPatch::DefaultedFormulaType)?;
+                        object.insert("type".to_owned(), json!("string"));
+                    }
+                }
+            }
+            // Notion sometimes just sets title to an empty object? Guess we have to set it to something?
+            "title" => {
+                if let Value::Null = value {
+                    patch(key, PropertyPatch::DefaultedTitle)?;
+                    *value = json!([{
+                      "type": "text",
+                      "text": {
+                        "content": "Unknown title",
+                        "link": null
+                      },
+                      "annotations": {
+                        "bold": false,
+                        "italic": false,
+                        "strikethrough": false,
+                        "underline": false,
+                        "code": false,
+                        "color": "default"
+                      },
+                      "plain_text": "Unknown title",
+                      "href": null
+                    }])
+                }
+            }
+            // Notion sometimes forget to set a value for a checkbox??
+            "checkbox" => {
+                if let Value::Null = value {
+                    patch(key, PropertyPatch::DefaultedCheckbox)?;
+                    *value = json!(false)
+                }
+            }
+            _ => {}
+        };
+    }
+
+    Ok(())
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -1278,7 +3942,7 @@ pub enum RichText {
         annotations: Annotations,
     },
     Equation {
-        expression: Option<String>,
+        equation: Equation,
         plain_text: String,
         href: Option<String>,
         annotations: Annotations,
@@ -1383,7 +4047,7 @@ impl std::fmt::Display for DateValue {
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct Equation {
-    pub plain_text: String,
+    pub expression: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -1431,9 +4095,20 @@ pub enum Color {
 #[serde(tag = "type")]
 #[serde(rename_all = "snake_case")]
 pub enum Parent {
-    PageId { page_id: String },
-    DatabaseId { database_id: String },
-    BlockId { block_id: String },
+    PageId {
+        page_id: String,
+    },
+    DatabaseId {
+        database_id: String,
+    },
+    /// Pages in databases have data source parents since `ApiVersion::V2025_09_03`.
+    DataSourceId {
+        data_source_id: String,
+        database_id: Option<String>,
+    },
+    BlockId {
+        block_id: String,
+    },
     Workspace,
 }
 
@@ -1441,8 +4116,39 @@ pub enum Parent {
 #[serde(tag = "type")]
 #[serde(rename_all = "lowercase")]
 pub enum File {
-    File { file: NotionFile },
-    External { external: ExternalFile },
+    File {
+        file: NotionFile,
+    },
+    External {
+        external: ExternalFile,
+    },
+    /// A file uploaded with `FileUploads`, to attach it to a block or a files property.
+    #[serde(rename = "file_upload")]
+    FileUpload {
+        file_upload: FileUploadReference,
+    },
+}
+
+impl File {
+    /// Empty for a `File::FileUpload`, which Notion turns into a `File::File` once attached.
+    pub fn url(&self) -> &str {
+        match self {
+            File::File { file } => &file.url,
+            File::External { external } => &external.url,
+            File::FileUpload { .. } => "",
+        }
+    }
+
+    /// The last path segment of the URL, or the whole URL if it has none.
+    pub fn name(&self) -> &str {
+        let url = self.url();
+        let path = url.split(['?', '#']).next().unwrap_or(url);
+
+        match path.trim_end_matches('/').rsplit('/').next() {
+            Some(name) if !name.is_empty() => name,
+            _ => url,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -1454,6 +4160,19 @@ pub enum Icon {
     External { external: ExternalFile },
 }
 
+impl Icon {
+    /// The icon as a `File`, unless it is an emoji.
+    pub fn file(&self) -> Option<File> {
+        match self {
+            Icon::Emoji { .. } => None,
+            Icon::File { file } => Some(File::File { file: file.clone() }),
+            Icon::External { external } => Some(File::External {
+                external: external.clone(),
+            }),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct NotionFile {
     pub expiry_time: DateValue,
@@ -1465,6 +4184,51 @@ pub struct ExternalFile {
     pub url: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FileUploadReference {
+    pub id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FileUpload {
+    pub id: String,
+    pub status: FileUploadStatus,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    pub content_length: Option<u64>,
+    /// Uploads that aren't attached before they expire are discarded.
+    pub expiry_time: Option<DateValue>,
+    pub number_of_parts: Option<FileUploadParts>,
+    pub created_time: DateValue,
+    pub last_edited_time: DateValue,
+}
+
+impl FileUpload {
+    /// The file to put into an `Image`, `File`, `Pdf` or `Video` block, or a files property.
+    pub fn file(&self) -> File {
+        File::FileUpload {
+            file_upload: FileUploadReference {
+                id: self.id.clone(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileUploadStatus {
+    Pending,
+    Uploaded,
+    Expired,
+    Failed,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct FileUploadParts {
+    pub total: usize,
+    pub sent: usize,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum DatabaseFormulaType {
@@ -0,0 +1,472 @@
+//! Conversions between page properties and plain Rust values, used by `#[derive(NotionPage)]`
+//! and `#[derive(NotionSelect)]`.
+
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+pub use serde_json::{json, Map, Value};
+
+use crate::{Date, DateValue, Error, Formula, Page, Property, Result, RichText};
+
+/// A struct that maps onto the properties of a page.
+pub trait NotionPage: for<'a> TryFrom<&'a Page, Error = Error> {
+    /// The `properties` object to send when creating or updating a page.
+    fn to_properties(&self) -> Map<String, Value>;
+}
+
+/// The property types a value can be written as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PropertyKind {
+    Checkbox,
+    Date,
+    Email,
+    MultiSelect,
+    Number,
+    People,
+    PhoneNumber,
+    Relation,
+    RichText,
+    Select,
+    Status,
+    Title,
+    Url,
+}
+
+impl PropertyKind {
+    pub fn key(&self) -> &'static str {
+        match self {
+            PropertyKind::Checkbox => "checkbox",
+            PropertyKind::Date => "date",
+            PropertyKind::Email => "email",
+            PropertyKind::MultiSelect => "multi_select",
+            PropertyKind::Number => "number",
+            PropertyKind::People => "people",
+            PropertyKind::PhoneNumber => "phone_number",
+            PropertyKind::Relation => "relation",
+            PropertyKind::RichText => "rich_text",
+            PropertyKind::Select => "select",
+            PropertyKind::Status => "status",
+            PropertyKind::Title => "title",
+            PropertyKind::Url => "url",
+        }
+    }
+
+    fn empty(&self) -> Value {
+        match self {
+            // Checkboxes can't be empty, and Notion rejects `null`
+            PropertyKind::Checkbox => json!(false),
+            PropertyKind::MultiSelect
+            | PropertyKind::People
+            | PropertyKind::Relation
+            | PropertyKind::RichText
+            | PropertyKind::Title => json!([]),
+            _ => Value::Null,
+        }
+    }
+}
+
+/// Returned by `FromProperty` when a property can't be read into the value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyValueError {
+    /// The property is of a type the value can't be read from.
+    WrongType,
+    /// A number that isn't a whole number in the range of the integer type.
+    NumberOutOfRange(f64),
+    /// A select, status or multi select option without a variant.
+    UnknownOption(String),
+}
+
+impl PropertyValueError {
+    fn into_error(self, name: &str) -> Error {
+        let property = name.to_owned();
+
+        match self {
+            PropertyValueError::WrongType => Error::UnexpectedPropertyType(property),
+            PropertyValueError::NumberOutOfRange(number) => {
+                Error::NumberOutOfRange { property, number }
+            }
+            PropertyValueError::UnknownOption(option) => Error::UnknownOption { property, option },
+        }
+    }
+}
+
+/// A value that can be read out of a page property.
+pub trait FromProperty: Sized {
+    /// Returns `Ok(None)` when the property is of a compatible type but has no value.
+    fn from_property(property: &Property) -> std::result::Result<Option<Self>, PropertyValueError>;
+}
+
+/// A value that can be written into a page property.
+pub trait ToProperty {
+    /// The property type used when the field doesn't specify a `kind`.
+    const KIND: PropertyKind;
+
+    /// Returns the value stored under the `kind` key of the property object.
+    fn to_property(&self, kind: PropertyKind) -> Value;
+}
+
+/// A value that can be named by a select, status or multi select option.
+pub trait SelectValue: Sized {
+    fn from_option_name(name: &str) -> Option<Self>;
+    fn option_name(&self) -> String;
+}
+
+/// Reads the property `name` (or the property with id `id`) of `page` into a field.
+pub trait FromPropertyField: Sized {
+    fn from_field(name: &str, property: Option<&Property>) -> Result<Self>;
+}
+
+impl<T: FromProperty> FromPropertyField for T {
+    fn from_field(name: &str, property: Option<&Property>) -> Result<Self> {
+        let property = property.ok_or_else(|| Error::MissingProperty(name.to_owned()))?;
+
+        match T::from_property(property) {
+            Ok(Some(value)) => Ok(value),
+            Ok(None) => Err(Error::MissingProperty(name.to_owned())),
+            Err(error) => Err(error.into_error(name)),
+        }
+    }
+}
+
+impl<T: FromProperty> FromPropertyField for Option<T> {
+    fn from_field(name: &str, property: Option<&Property>) -> Result<Self> {
+        match property.map(T::from_property) {
+            None | Some(Ok(None)) => Ok(None),
+            Some(Ok(Some(value))) => Ok(Some(value)),
+            Some(Err(error)) => Err(error.into_error(name)),
+        }
+    }
+}
+
+impl<T: ToProperty> ToProperty for Option<T> {
+    const KIND: PropertyKind = T::KIND;
+
+    fn to_property(&self, kind: PropertyKind) -> Value {
+        match self {
+            Some(value) => value.to_property(kind),
+            None => kind.empty(),
+        }
+    }
+}
+
+/// Looks up a property by name, falling back to looking it up by id.
+pub fn find_property<'a>(
+    page: &'a Page,
+    name: Option<&str>,
+    id: Option<&str>,
+) -> Option<&'a Property> {
+    name.and_then(|name| page.properties.get(name)).or_else(|| {
+        id.and_then(|id| page.get_property_by_id(id))
+            .map(|(_, property)| property)
+    })
+}
+
+pub fn plain_text(rich_text: &[RichText]) -> String {
+    rich_text
+        .iter()
+        .map(|rich_text| match rich_text {
+            RichText::Text { plain_text, .. }
+            | RichText::Mention { plain_text, .. }
+            | RichText::Equation { plain_text, .. } => plain_text.as_str(),
+        })
+        .collect()
+}
+
+fn text(content: &str) -> Value {
+    json!([{ "type": "text", "text": { "content": content } }])
+}
+
+impl FromProperty for String {
+    fn from_property(property: &Property) -> std::result::Result<Option<Self>, PropertyValueError> {
+        Ok(match property {
+            Property::Title { title: text, .. }
+            | Property::RichText {
+                rich_text: text, ..
+            } => Some(plain_text(text)),
+            Property::Url { url: value, .. }
+            | Property::Email { email: value, .. }
+            | Property::PhoneNumber {
+                phone_number: value,
+                ..
+            } => value.clone(),
+            Property::Select { select: option, .. } | Property::Status { status: option, .. } => {
+                option.as_ref().map(|option| option.name.clone())
+            }
+            Property::Formula {
+                formula: Formula::String { string },
+                ..
+            } => string.clone(),
+            _ => return Err(PropertyValueError::WrongType),
+        })
+    }
+}
+
+impl ToProperty for String {
+    const KIND: PropertyKind = PropertyKind::RichText;
+
+    fn to_property(&self, kind: PropertyKind) -> Value {
+        match kind {
+            PropertyKind::Title | PropertyKind::RichText => text(self),
+            PropertyKind::Select | PropertyKind::Status => json!({ "name": self }),
+            PropertyKind::MultiSelect => json!([{ "name": self }]),
+            PropertyKind::People | PropertyKind::Relation => json!([{ "id": self }]),
+            _ => json!(self),
+        }
+    }
+}
+
+impl SelectValue for String {
+    fn from_option_name(name: &str) -> Option<Self> {
+        Some(name.to_owned())
+    }
+
+    fn option_name(&self) -> String {
+        self.clone()
+    }
+}
+
+impl FromProperty for bool {
+    fn from_property(property: &Property) -> std::result::Result<Option<Self>, PropertyValueError> {
+        match property {
+            Property::Checkbox { checkbox, .. } => Ok(Some(*checkbox)),
+            Property::Formula {
+                formula: Formula::Boolean { boolean },
+                ..
+            } => Ok(*boolean),
+            _ => Err(PropertyValueError::WrongType),
+        }
+    }
+}
+
+impl ToProperty for bool {
+    const KIND: PropertyKind = PropertyKind::Checkbox;
+
+    fn to_property(&self, _: PropertyKind) -> Value {
+        json!(self)
+    }
+}
+
+fn number_of(property: &Property) -> std::result::Result<Option<f64>, PropertyValueError> {
+    match property {
+        Property::Number { number, .. }
+        | Property::Formula {
+            formula: Formula::Number { number },
+            ..
+        } => Ok(number.map(f64::from)),
+        _ => Err(PropertyValueError::WrongType),
+    }
+}
+
+macro_rules! impl_number {
+    ($($number:ty),*) => {
+        $(
+            impl ToProperty for $number {
+                const KIND: PropertyKind = PropertyKind::Number;
+
+                fn to_property(&self, _: PropertyKind) -> Value {
+                    json!(self)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_float {
+    ($($number:ty),*) => {
+        $(
+            impl FromProperty for $number {
+                fn from_property(property: &Property) -> std::result::Result<Option<Self>, PropertyValueError> {
+                    Ok(number_of(property)?.map(|number| number as $number))
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_integer {
+    ($($number:ty),*) => {
+        $(
+            impl FromProperty for $number {
+                fn from_property(property: &Property) -> std::result::Result<Option<Self>, PropertyValueError> {
+                    number_of(property)?
+                        .map(|number| {
+                            // `MAX as f64` rounds up for 64-bit types, hence the exclusive bound
+                            let in_range = number >= <$number>::MIN as f64
+                                && number < <$number>::MAX as f64 + 1.0;
+
+                            if number.fract() == 0.0 && in_range {
+                                Ok(number as $number)
+                            } else {
+                                Err(PropertyValueError::NumberOutOfRange(number))
+                            }
+                        })
+                        .transpose()
+                }
+            }
+        )*
+    };
+}
+
+impl_number!(f32, f64, i32, i64, u32, u64);
+impl_float!(f32, f64);
+impl_integer!(i32, i64, u32, u64);
+
+fn date_of(property: &Property) -> std::result::Result<Option<Date>, PropertyValueError> {
+    match property {
+        Property::Date { date, .. }
+        | Property::Formula {
+            formula: Formula::Date { date },
+            ..
+        } => Ok(date.clone()),
+        Property::CreatedTime { created_time, .. } => Ok(Some(Date {
+            start: created_time.clone(),
+            end: None,
+            time_zone: None,
+        })),
+        Property::LastEditedTime {
+            last_edited_time, ..
+        } => Ok(Some(Date {
+            start: last_edited_time.clone(),
+            end: None,
+            time_zone: None,
+        })),
+        _ => Err(PropertyValueError::WrongType),
+    }
+}
+
+impl FromProperty for Date {
+    fn from_property(property: &Property) -> std::result::Result<Option<Self>, PropertyValueError> {
+        date_of(property)
+    }
+}
+
+impl ToProperty for Date {
+    const KIND: PropertyKind = PropertyKind::Date;
+
+    fn to_property(&self, _: PropertyKind) -> Value {
+        json!({
+            "start": self.start,
+            "end": self.end,
+            "time_zone": self.time_zone,
+        })
+    }
+}
+
+impl FromProperty for DateTime<Utc> {
+    fn from_property(property: &Property) -> std::result::Result<Option<Self>, PropertyValueError> {
+        Ok(date_of(property)?.map(|date| match date.start {
+            DateValue::DateTime(date_time) => date_time,
+            DateValue::Date(date) => date.and_time(NaiveTime::MIN).and_utc(),
+        }))
+    }
+}
+
+impl ToProperty for DateTime<Utc> {
+    const KIND: PropertyKind = PropertyKind::Date;
+
+    fn to_property(&self, _: PropertyKind) -> Value {
+        json!({ "start": self.to_rfc3339() })
+    }
+}
+
+impl FromProperty for NaiveDate {
+    fn from_property(property: &Property) -> std::result::Result<Option<Self>, PropertyValueError> {
+        Ok(date_of(property)?.map(|date| match date.start {
+            DateValue::DateTime(date_time) => date_time.date_naive(),
+            DateValue::Date(date) => date,
+        }))
+    }
+}
+
+impl ToProperty for NaiveDate {
+    const KIND: PropertyKind = PropertyKind::Date;
+
+    fn to_property(&self, _: PropertyKind) -> Value {
+        json!({ "start": self.format("%Y-%m-%d").to_string() })
+    }
+}
+
+/// Multi select option names, or the ids of the pages of a relation or the users of people.
+impl<T: SelectValue> FromProperty for Vec<T> {
+    fn from_property(property: &Property) -> std::result::Result<Option<Self>, PropertyValueError> {
+        let names = match property {
+            Property::MultiSelect { multi_select, .. } => multi_select
+                .iter()
+                .map(|option| option.name.as_str())
+                .collect::<Vec<_>>(),
+            Property::Relation { relation, .. } => {
+                relation.iter().map(|page| page.id.as_str()).collect()
+            }
+            Property::People { people, .. } => people.iter().map(|user| user.id.as_str()).collect(),
+            _ => return Err(PropertyValueError::WrongType),
+        };
+
+        names
+            .into_iter()
+            .map(|name| {
+                T::from_option_name(name)
+                    .ok_or_else(|| PropertyValueError::UnknownOption(name.to_owned()))
+            })
+            .collect::<std::result::Result<Vec<T>, _>>()
+            .map(Some)
+    }
+}
+
+impl<T: SelectValue> ToProperty for Vec<T> {
+    const KIND: PropertyKind = PropertyKind::MultiSelect;
+
+    fn to_property(&self, kind: PropertyKind) -> Value {
+        let key = match kind {
+            PropertyKind::People | PropertyKind::Relation => "id",
+            _ => "name",
+        };
+
+        self.iter()
+            .map(|value| json!({ key: value.option_name() }))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The property Notion returns after `value` was written as `kind`.
+    fn written<T: ToProperty>(value: &T, kind: PropertyKind) -> Property {
+        let mut property = json!({ "id": "a%3Bc", "type": kind.key() });
+        property[kind.key()] = value.to_property(kind);
+
+        serde_json::from_value(property).unwrap()
+    }
+
+    #[test]
+    fn relations_and_people_round_trip() {
+        let ids = vec!["59833787-2cf9-4fdf-8782-e53db20768a5".to_owned()];
+
+        for kind in [PropertyKind::Relation, PropertyKind::People] {
+            assert_eq!(
+                ids.to_property(kind),
+                json!([{ "id": "59833787-2cf9-4fdf-8782-e53db20768a5" }])
+            );
+            assert_eq!(
+                Vec::<String>::from_field("Links", Some(&written(&ids, kind))).unwrap(),
+                ids
+            );
+            assert_eq!(Vec::<String>::new().to_property(kind), json!([]));
+        }
+    }
+
+    #[test]
+    fn optional_checkboxes_are_written_unchecked() {
+        assert_eq!(Some(true).to_property(PropertyKind::Checkbox), json!(true));
+        assert_eq!(
+            None::<bool>.to_property(PropertyKind::Checkbox),
+            json!(false)
+        );
+
+        let unchecked = written(&None::<bool>, PropertyKind::Checkbox);
+        assert_eq!(
+            Option::<bool>::from_field("Done", Some(&unchecked)).unwrap(),
+            Some(false)
+        );
+        assert_eq!(Option::<bool>::from_field("Done", None).unwrap(), None);
+    }
+}
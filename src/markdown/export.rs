@@ -0,0 +1,585 @@
+//! Rendering of block trees as Markdown.
+//!
+//! Children are read from the `children` fields of the blocks, so they need to be fetched
+//! beforehand. Blocks that have no Markdown equivalent are rendered as HTML comments.
+
+use crate::{Annotations, Block, BlockType, CodeLanguage, RichText};
+
+/// Renders `blocks` (and their children) as a Markdown document.
+pub fn blocks_to_markdown(blocks: &[Block]) -> String {
+    let mut markdown = render_blocks(blocks);
+
+    if !markdown.is_empty() {
+        markdown.push('\n');
+    }
+
+    markdown
+}
+
+/// Renders `rich_text` as inline Markdown.
+pub fn rich_text_to_markdown(rich_text: &[RichText]) -> String {
+    rich_text.iter().map(render_rich_text).collect()
+}
+
+#[derive(PartialEq, Clone, Copy)]
+enum List {
+    Bulleted,
+    Numbered,
+}
+
+fn render_blocks(blocks: &[Block]) -> String {
+    let mut output = String::new();
+    let mut previous = None;
+    let mut number = 0;
+
+    for block in blocks {
+        let list = match block.block {
+            BlockType::BulletedListItem { .. } | BlockType::ToDo { .. } => Some(List::Bulleted),
+            BlockType::NumberedListItem { .. } => Some(List::Numbered),
+            _ => None,
+        };
+
+        number = match (list, previous) {
+            (Some(List::Numbered), Some(List::Numbered)) => number + 1,
+            _ => 1,
+        };
+
+        let rendered = render_block(block, number);
+        if rendered.is_empty() {
+            continue;
+        }
+
+        if !output.is_empty() {
+            // Keep consecutive items in the same (tight) list
+            output.push_str(if list.is_some() && list == previous {
+                "\n"
+            } else {
+                "\n\n"
+            });
+        }
+        output.push_str(&rendered);
+        previous = list;
+    }
+
+    output
+}
+
+fn render_block(block: &Block, number: usize) -> String {
+    match &block.block {
+        BlockType::Paragraph { paragraph } => with_children(
+            rich_text_to_markdown(&paragraph.rich_text),
+            render_children(block, &paragraph.children),
+        ),
+        BlockType::Heading1 { heading } => with_children(
+            format!("# {}", rich_text_to_markdown(&heading.rich_text)),
+            render_children(block, &heading.children),
+        ),
+        BlockType::Heading2 { heading } => with_children(
+            format!("## {}", rich_text_to_markdown(&heading.rich_text)),
+            render_children(block, &heading.children),
+        ),
+        BlockType::Heading3 { heading } => with_children(
+            format!("### {}", rich_text_to_markdown(&heading.rich_text)),
+            render_children(block, &heading.children),
+        ),
+        BlockType::BulletedListItem { bulleted_list_item } => list_item(
+            "- ",
+            &bulleted_list_item.rich_text,
+            render_children(block, &bulleted_list_item.children),
+        ),
+        BlockType::NumberedListItem { numbered_list_item } => list_item(
+            &format!("{number}. "),
+            &numbered_list_item.rich_text,
+            render_children(block, &numbered_list_item.children),
+        ),
+        BlockType::ToDo { to_do } => list_item(
+            if to_do.checked.unwrap_or_default() {
+                "- [x] "
+            } else {
+                "- [ ] "
+            },
+            &to_do.rich_text,
+            render_children(block, &to_do.children),
+        ),
+        BlockType::Toggle { toggle } => {
+            let summary = rich_text_to_markdown(&toggle.rich_text);
+
+            match render_children(block, &toggle.children) {
+                Some(children) => {
+                    format!("<details>\n<summary>{summary}</summary>\n\n{children}\n\n</details>")
+                }
+                None => format!("<details>\n<summary>{summary}</summary>\n</details>"),
+            }
+        }
+        BlockType::Quote { quote } => blockquote(&with_children(
+            rich_text_to_markdown(&quote.rich_text),
+            render_children(block, &quote.children),
+        )),
+        BlockType::Callout { callout } => {
+            let icon = match &callout.icon {
+                Some(crate::Icon::Emoji { emoji }) => format!("{emoji} "),
+                _ => String::new(),
+            };
+
+            blockquote(&with_children(
+                format!("{icon}{}", rich_text_to_markdown(&callout.rich_text)),
+                render_children(block, &callout.children),
+            ))
+        }
+        BlockType::Code { code } => {
+            let content = crate::mapping::plain_text(&code.rich_text);
+            let fence = "`".repeat(longest_run(&content, '`').max(2) + 1);
+
+            format!("{fence}{}\n{content}\n{fence}", fence_info(&code.language))
+        }
+        BlockType::Equation { equation } => format!("$$\n{}\n$$", equation.expression),
+        BlockType::Divider => "---".to_owned(),
+        BlockType::Image { image } => format!("![]({})", escape_url(image.url())),
+        BlockType::Video { video: file }
+        | BlockType::Pdf { pdf: file }
+        | BlockType::File { file } => {
+            format!("[{}]({})", escape(file.name()), escape_url(file.url()))
+        }
+        BlockType::Bookmark { bookmark } => {
+            let caption = rich_text_to_markdown(&bookmark.caption);
+            let text = if caption.is_empty() {
+                escape(&bookmark.url)
+            } else {
+                caption
+            };
+
+            format!("[{text}]({})", escape_url(&bookmark.url))
+        }
+        BlockType::Embed {
+            embed: crate::Embed { url },
+        }
+        | BlockType::LinkPreview {
+            link_preview: crate::LinkPreview { url },
+        } => format!("[{}]({})", escape(url), escape_url(url)),
+        BlockType::ChildPage {
+            child_page: crate::ChildPage { title, .. },
+        }
+        | BlockType::ChildDatabase {
+            child_database: crate::ChildDatabase { title, .. },
+        } => format!(
+            "[{}](https://www.notion.so/{})",
+            escape(title),
+            block.id.replace('-', "")
+        ),
+        BlockType::Table { table } => match &table.children {
+            Some(rows) => render_table(table.table_width as usize, table.has_column_header, rows),
+            None => render_children(block, &None).unwrap_or_default(),
+        },
+        BlockType::ColumnList { column_list } => {
+            render_children(block, &column_list.children).unwrap_or_default()
+        }
+        BlockType::Column { column } => {
+            render_children(block, &column.children).unwrap_or_default()
+        }
+        BlockType::Breadcrumb
+        | BlockType::LinkToPage
+        | BlockType::SyncedBlock
+        | BlockType::TableOfContents
+        | BlockType::TableRow { .. }
+        | BlockType::Template
+        | BlockType::Unsupported => unsupported(block),
+    }
+}
+
+fn render_children(block: &Block, children: &Option<Vec<Block>>) -> Option<String> {
+    match children {
+        Some(children) if !children.is_empty() => Some(render_blocks(children)),
+        Some(_) => None,
+        None if block.has_children => Some(format!(
+            "<!-- children of block {} were not fetched -->",
+            block.id
+        )),
+        None => None,
+    }
+}
+
+fn unsupported(block: &Block) -> String {
+    let kind = serde_json::to_value(&block.block)
+        .ok()
+        .and_then(|value| value.get("type")?.as_str().map(str::to_owned))
+        .unwrap_or_else(|| "unknown".to_owned());
+
+    format!("<!-- unsupported block: {kind} ({}) -->", block.id)
+}
+
+fn with_children(text: String, children: Option<String>) -> String {
+    match children {
+        Some(children) if text.is_empty() => children,
+        Some(children) => format!("{text}\n\n{children}"),
+        None => text,
+    }
+}
+
+fn list_item(marker: &str, rich_text: &[RichText], children: Option<String>) -> String {
+    let text = format!("{marker}{}", rich_text_to_markdown(rich_text));
+
+    match children {
+        Some(children) => {
+            let indentation = " ".repeat(marker.chars().count());
+            let children = children
+                .lines()
+                .map(|line| {
+                    if line.is_empty() {
+                        String::new()
+                    } else {
+                        format!("{indentation}{line}")
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            format!("{text}\n{children}")
+        }
+        None => text,
+    }
+}
+
+fn blockquote(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            if line.is_empty() {
+                ">".to_owned()
+            } else {
+                format!("> {line}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn render_table(width: usize, has_column_header: bool, rows: &[Block]) -> String {
+    let mut rows = rows
+        .iter()
+        .filter_map(|row| match &row.block {
+            BlockType::TableRow { table_row } => Some(
+                (0..width)
+                    .map(|index| {
+                        table_row
+                            .cells
+                            .get(index)
+                            .map(|cell| table_cell(cell))
+                            .unwrap_or_default()
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .into_iter();
+
+    // GFM tables always need a header row, so tables without one get an empty one
+    let header = if has_column_header {
+        rows.next().unwrap_or_else(|| vec![String::new(); width])
+    } else {
+        vec![String::new(); width]
+    };
+
+    let mut lines = vec![
+        table_line(&header),
+        table_line(&vec!["---".to_owned(); width]),
+    ];
+    lines.extend(rows.map(|row| table_line(&row)));
+
+    lines.join("\n")
+}
+
+fn table_cell(rich_text: &[RichText]) -> String {
+    rich_text_to_markdown(rich_text)
+        .replace("\\|", "|")
+        .replace('|', "\\|")
+        .replace("\\\n", "<br>")
+}
+
+fn table_line(cells: &[String]) -> String {
+    format!("| {} |", cells.join(" | "))
+}
+
+fn render_rich_text(rich_text: &RichText) -> String {
+    match rich_text {
+        RichText::Text {
+            text,
+            href,
+            annotations,
+            ..
+        } => {
+            let link = text
+                .link
+                .as_ref()
+                .map(|link| link.url.as_str())
+                .or(href.as_deref());
+
+            decorate(&text.content, annotations, link)
+        }
+        RichText::Mention {
+            plain_text,
+            href,
+            annotations,
+            ..
+        } => decorate(plain_text, annotations, href.as_deref()),
+        RichText::Equation { equation, .. } => format!("${}$", equation.expression),
+    }
+}
+
+fn decorate(content: &str, annotations: &Annotations, link: Option<&str>) -> String {
+    let trimmed = content.trim();
+    if trimmed.is_empty() {
+        return content.to_owned();
+    }
+
+    // Emphasis markers can't be next to whitespace on the inside, so it goes on the outside
+    let leading = &content[..content.len() - content.trim_start().len()];
+    let trailing = &content[content.trim_end().len()..];
+
+    let mut inner = if annotations.code {
+        code_span(trimmed)
+    } else {
+        escape(trimmed)
+    };
+
+    if annotations.strikethrough {
+        inner = format!("~~{inner}~~");
+    }
+    if annotations.italic {
+        inner = format!("*{inner}*");
+    }
+    if annotations.bold {
+        inner = format!("**{inner}**");
+    }
+    if let Some(url) = link {
+        inner = format!("[{inner}]({})", escape_url(url));
+    }
+
+    format!("{leading}{inner}{trailing}")
+}
+
+fn code_span(content: &str) -> String {
+    let fence = "`".repeat(longest_run(content, '`') + 1);
+    let padding = if content.starts_with('`') || content.ends_with('`') {
+        " "
+    } else {
+        ""
+    };
+
+    format!("{fence}{padding}{content}{padding}{fence}")
+}
+
+fn longest_run(content: &str, character: char) -> usize {
+    content
+        .split(|other| other != character)
+        .map(str::len)
+        .max()
+        .unwrap_or_default()
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for (index, character) in text.chars().enumerate() {
+        match character {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|' | '~' => {
+                escaped.push('\\');
+                escaped.push(character);
+            }
+            '#' | '-' | '+' if index == 0 => {
+                escaped.push('\\');
+                escaped.push(character);
+            }
+            // Hard line break
+            '\n' => escaped.push_str("\\\n"),
+            _ => escaped.push(character),
+        }
+    }
+
+    escaped
+}
+
+fn escape_url(url: &str) -> String {
+    url.replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29")
+}
+
+fn fence_info(language: &CodeLanguage) -> String {
+    match language {
+        CodeLanguage::PlainText | CodeLanguage::JavaCCppCSharp => String::new(),
+        CodeLanguage::Cpp => "cpp".to_owned(),
+        CodeLanguage::CSharp => "csharp".to_owned(),
+        CodeLanguage::FSharp => "fsharp".to_owned(),
+        CodeLanguage::ObjectiveC => "objectivec".to_owned(),
+        CodeLanguage::VBNet => "vbnet".to_owned(),
+        CodeLanguage::VisualBasic => "vb".to_owned(),
+        language => serde_json::to_value(language)
+            .ok()
+            .and_then(|value| value.as_str().map(str::to_owned))
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn block(id: &str, block: Value) -> Block {
+        let mut value = json!({
+            "id": id,
+            "parent": { "type": "page_id", "page_id": "59833787-2cf9-4fdf-8782-e53db20768a5" },
+            "created_time": "2022-03-01T19:05:00.000Z",
+            "last_edited_time": "2022-07-06T20:25:00.000Z",
+            "created_by": { "id": "ee5f0f84-409a-440f-983a-a5315961c6e4" },
+            "last_edited_by": { "id": "ee5f0f84-409a-440f-983a-a5315961c6e4" },
+            "has_children": false,
+            "archived": false
+        });
+        value
+            .as_object_mut()
+            .unwrap()
+            .extend(block.as_object().unwrap().clone());
+
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn text(content: &str, annotations: Value) -> Value {
+        let mut base = json!({
+            "bold": false,
+            "italic": false,
+            "strikethrough": false,
+            "underline": false,
+            "code": false,
+            "color": "default"
+        });
+        base.as_object_mut()
+            .unwrap()
+            .extend(annotations.as_object().unwrap().clone());
+
+        json!({
+            "type": "text",
+            "text": { "content": content, "link": null },
+            "annotations": base,
+            "plain_text": content,
+            "href": null
+        })
+    }
+
+    #[test]
+    fn renders_rich_text_annotations() {
+        let rich_text = serde_json::from_value::<Vec<RichText>>(json!([
+            text("Hello ", json!({ "bold": true })),
+            text("a_b", json!({ "italic": true })),
+            text(" and ", json!({})),
+            text("x`y", json!({ "code": true })),
+            {
+                "type": "equation",
+                "equation": { "expression": "e=mc^2" },
+                "annotations": base_annotations(),
+                "plain_text": "e=mc^2",
+                "href": null
+            }
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            rich_text_to_markdown(&rich_text),
+            "**Hello** *a\\_b* and ``x`y``$e=mc^2$"
+        );
+    }
+
+    fn base_annotations() -> Value {
+        text("", json!({}))["annotations"].clone()
+    }
+
+    #[test]
+    fn renders_lists_code_and_tables() {
+        let mut parent = block(
+            "a",
+            json!({
+                "type": "bulleted_list_item",
+                "bulleted_list_item": { "color": "default", "rich_text": [text("Parent", json!({}))] }
+            }),
+        );
+        if let BlockType::BulletedListItem { bulleted_list_item } = &mut parent.block {
+            bulleted_list_item.children = Some(vec![block(
+                "b",
+                json!({
+                    "type": "to_do",
+                    "to_do": { "color": "default", "checked": true, "rich_text": [text("Done", json!({}))] }
+                }),
+            )]);
+        }
+
+        let mut table = block(
+            "c",
+            json!({
+                "type": "table",
+                "table": { "table_width": 2, "has_column_header": true, "has_row_header": false }
+            }),
+        );
+        if let BlockType::Table { table } = &mut table.block {
+            table.children = Some(vec![
+                block(
+                    "d",
+                    json!({
+                        "type": "table_row",
+                        "table_row": { "cells": [[text("Name", json!({}))], [text("Value", json!({}))]] }
+                    }),
+                ),
+                block(
+                    "e",
+                    json!({
+                        "type": "table_row",
+                        "table_row": { "cells": [[text("a|b", json!({}))], []] }
+                    }),
+                ),
+            ]);
+        }
+
+        let blocks = vec![
+            block(
+                "f",
+                json!({
+                    "type": "heading_1",
+                    "heading_1": { "color": "default", "is_toggleable": false, "rich_text": [text("Title", json!({}))] }
+                }),
+            ),
+            parent,
+            block(
+                "g",
+                json!({
+                    "type": "numbered_list_item",
+                    "numbered_list_item": { "color": "default", "rich_text": [text("One", json!({}))] }
+                }),
+            ),
+            block(
+                "h",
+                json!({
+                    "type": "numbered_list_item",
+                    "numbered_list_item": { "color": "default", "rich_text": [text("Two", json!({}))] }
+                }),
+            ),
+            block(
+                "i",
+                json!({
+                    "type": "code",
+                    "code": { "caption": [], "language": "rust", "rich_text": [text("fn main() {}", json!({}))] }
+                }),
+            ),
+            table,
+            block("j", json!({ "type": "breadcrumb", "breadcrumb": {} })),
+        ];
+
+        assert_eq!(
+            blocks_to_markdown(&blocks),
+            "# Title\n\n\
+             - Parent\n  - [x] Done\n\n\
+             1. One\n2. Two\n\n\
+             ```rust\nfn main() {}\n```\n\n\
+             | Name | Value |\n| --- | --- |\n| a\\|b |  |\n\n\
+             <!-- unsupported block: breadcrumb (j) -->\n"
+        );
+    }
+}
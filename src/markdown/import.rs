@@ -0,0 +1,640 @@
+//! Parsing of Markdown into block trees that can be passed to `BlockChildren::append`.
+
+use std::iter::Peekable;
+
+use pulldown_cmark::{
+    BlockQuoteKind, CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd,
+};
+use serde_json::Value;
+
+use crate::{
+    Annotations, Block, BlockType, Callout, Code, CodeLanguage, Color, Equation, ExternalFile,
+    File, Heading, Icon, Link, ListItem, Paragraph, Quote, RichText, Table, TableRow, Text,
+    ToDoItem,
+};
+
+// Notion rejects text objects with more than 2000 characters
+const MAX_TEXT_LENGTH: usize = 2000;
+
+/// Parses `markdown` (CommonMark with GFM tables, task lists, strikethrough, alerts and `$` math)
+/// into blocks. Nested content ends up in the `children` of the blocks.
+pub fn markdown_to_blocks(markdown: &str) -> Vec<BlockType> {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_MATH
+        | Options::ENABLE_GFM;
+
+    Importer {
+        events: Parser::new_ext(markdown, options).peekable(),
+    }
+    .blocks()
+}
+
+struct Importer<'a, I: Iterator<Item = Event<'a>>> {
+    events: Peekable<I>,
+}
+
+#[derive(Default)]
+struct Inline {
+    rich_text: Vec<RichText>,
+    // Blocks that Markdown allows inline but Notion doesn't, like images
+    blocks: Vec<BlockType>,
+    bold: usize,
+    italic: usize,
+    strikethrough: usize,
+    links: Vec<String>,
+    in_image: bool,
+}
+
+impl Inline {
+    fn push(&mut self, content: &str, code: bool) {
+        let annotations = Annotations {
+            bold: self.bold > 0,
+            italic: self.italic > 0,
+            strikethrough: self.strikethrough > 0,
+            code,
+            ..Default::default()
+        };
+        let link = self.links.last().cloned();
+
+        if let Some(RichText::Text {
+            text,
+            plain_text,
+            href,
+            annotations: previous,
+        }) = self.rich_text.last_mut()
+        {
+            if previous == &annotations && href == &link {
+                text.content.push_str(content);
+                plain_text.push_str(content);
+                return;
+            }
+        }
+
+        self.rich_text.push(text(content, annotations, link));
+    }
+
+    fn finish(self) -> (Vec<RichText>, Vec<BlockType>) {
+        (split_long_text(self.rich_text), self.blocks)
+    }
+}
+
+impl<'a, I: Iterator<Item = Event<'a>>> Importer<'a, I> {
+    /// Parses blocks until the end of the enclosing container, leaving its `End` event alone.
+    fn blocks(&mut self) -> Vec<BlockType> {
+        let mut blocks = Vec::new();
+
+        loop {
+            match self.events.peek() {
+                None | Some(Event::End(_)) => break,
+                Some(event) if is_inline(event) => {
+                    let (rich_text, extra) = self.inline();
+                    if !rich_text.is_empty() {
+                        blocks.push(paragraph(rich_text, Vec::new()));
+                    }
+                    blocks.extend(extra);
+                    continue;
+                }
+                _ => {}
+            }
+
+            match self.events.next() {
+                Some(Event::Start(Tag::Paragraph)) => {
+                    let (rich_text, extra) = self.inline();
+                    self.events.next();
+
+                    if !rich_text.is_empty() {
+                        blocks.push(paragraph(rich_text, Vec::new()));
+                    }
+                    blocks.extend(extra);
+                }
+                Some(Event::Start(Tag::Heading { level, .. })) => {
+                    let (rich_text, extra) = self.inline();
+                    self.events.next();
+
+                    let heading = Heading {
+                        color: Color::Default,
+                        rich_text,
+                        is_toggleable: false,
+                        children: None,
+                    };
+                    blocks.push(match level {
+                        HeadingLevel::H1 => BlockType::Heading1 { heading },
+                        HeadingLevel::H2 => BlockType::Heading2 { heading },
+                        _ => BlockType::Heading3 { heading },
+                    });
+                    blocks.extend(extra);
+                }
+                Some(Event::Start(Tag::BlockQuote(kind))) => {
+                    let mut children = self.blocks();
+                    self.events.next();
+
+                    let rich_text = take_leading_text(&mut children);
+                    blocks.push(match kind.map(alert_emoji) {
+                        Some(emoji) => BlockType::Callout {
+                            callout: Callout {
+                                icon: Some(Icon::Emoji {
+                                    emoji: emoji.to_owned(),
+                                }),
+                                color: Color::GrayBackground,
+                                rich_text,
+                                children: wrap(children),
+                            },
+                        },
+                        None => BlockType::Quote {
+                            quote: Quote {
+                                color: Color::Default,
+                                rich_text,
+                                children: wrap(children),
+                            },
+                        },
+                    });
+                }
+                Some(Event::Start(Tag::CodeBlock(kind))) => {
+                    let language = match kind {
+                        CodeBlockKind::Fenced(info) => code_language(&info),
+                        CodeBlockKind::Indented => CodeLanguage::PlainText,
+                    };
+                    let content = self.text();
+                    self.events.next();
+
+                    blocks.push(BlockType::Code {
+                        code: Code {
+                            caption: Vec::new(),
+                            rich_text: split_long_text(vec![text(
+                                content.strip_suffix('\n').unwrap_or(&content),
+                                Annotations::default(),
+                                None,
+                            )]),
+                            language,
+                        },
+                    });
+                }
+                Some(Event::Start(Tag::HtmlBlock)) => {
+                    // No way to render HTML in Notion, so keep the source around as text
+                    let content = self.text();
+                    self.events.next();
+
+                    let content = content.trim_end();
+                    if !content.is_empty() {
+                        blocks.push(paragraph(
+                            split_long_text(vec![text(content, Annotations::default(), None)]),
+                            Vec::new(),
+                        ));
+                    }
+                }
+                Some(Event::Start(Tag::List(start))) => {
+                    while let Some(Event::Start(Tag::Item)) = self.events.peek() {
+                        self.events.next();
+                        blocks.push(self.item(start.is_some()));
+                    }
+                    self.events.next();
+                }
+                Some(Event::Start(Tag::Table(_))) => blocks.push(self.table()),
+                Some(Event::Start(_)) => {
+                    // Footnote definitions, definition lists and the like: keep their content
+                    blocks.extend(self.blocks());
+                    self.events.next();
+                }
+                Some(Event::Rule) => blocks.push(BlockType::Divider),
+                _ => {}
+            }
+        }
+
+        blocks
+    }
+
+    fn item(&mut self, ordered: bool) -> BlockType {
+        let checked = match self.events.peek() {
+            Some(Event::TaskListMarker(checked)) => {
+                let checked = *checked;
+                self.events.next();
+                Some(checked)
+            }
+            _ => None,
+        };
+
+        // Tight list items have their text directly inside of the item
+        let (mut rich_text, mut children) = self.inline();
+        children.extend(self.blocks());
+        self.events.next();
+
+        if rich_text.is_empty() {
+            rich_text = take_leading_text(&mut children);
+        }
+
+        let children = wrap(children);
+
+        match (checked, ordered) {
+            (Some(checked), _) => BlockType::ToDo {
+                to_do: ToDoItem {
+                    color: Color::Default,
+                    rich_text,
+                    checked: Some(checked),
+                    children,
+                },
+            },
+            (None, true) => BlockType::NumberedListItem {
+                numbered_list_item: ListItem {
+                    color: Color::Default,
+                    rich_text,
+                    children,
+                },
+            },
+            (None, false) => BlockType::BulletedListItem {
+                bulleted_list_item: ListItem {
+                    color: Color::Default,
+                    rich_text,
+                    children,
+                },
+            },
+        }
+    }
+
+    fn table(&mut self) -> BlockType {
+        let mut rows = Vec::new();
+        let mut row = Vec::new();
+
+        while let Some(event) = self.events.next() {
+            match event {
+                Event::Start(Tag::TableCell) => {
+                    let (rich_text, _) = self.inline();
+                    self.events.next();
+                    row.push(rich_text);
+                }
+                Event::End(TagEnd::TableHead | TagEnd::TableRow) => {
+                    rows.push(std::mem::take(&mut row))
+                }
+                Event::End(TagEnd::Table) => break,
+                _ => {}
+            }
+        }
+
+        let width = rows.iter().map(Vec::len).max().unwrap_or_default();
+
+        BlockType::Table {
+            table: Table {
+                table_width: width as u32,
+                has_column_header: true,
+                has_row_header: false,
+                children: Some(
+                    rows.into_iter()
+                        .map(|mut cells| {
+                            cells.resize_with(width, Vec::new);
+                            Block::new(BlockType::TableRow {
+                                table_row: TableRow { cells },
+                            })
+                        })
+                        .collect(),
+                ),
+            },
+        }
+    }
+
+    /// Parses inline content until the end of the enclosing container or the start of a block,
+    /// leaving either of them alone.
+    fn inline(&mut self) -> (Vec<RichText>, Vec<BlockType>) {
+        let mut inline = Inline::default();
+
+        loop {
+            match self.events.peek() {
+                Some(event) if is_inline(event) => {}
+                _ => break,
+            }
+
+            match self.events.next() {
+                Some(Event::Text(content)) if !inline.in_image => inline.push(&content, false),
+                Some(Event::Code(content)) => inline.push(&content, true),
+                Some(Event::InlineMath(expression)) => inline.rich_text.push(RichText::Equation {
+                    equation: Equation {
+                        expression: expression.to_string(),
+                    },
+                    plain_text: expression.to_string(),
+                    href: None,
+                    annotations: Annotations::default(),
+                }),
+                Some(Event::DisplayMath(expression)) => inline.blocks.push(BlockType::Equation {
+                    equation: Equation {
+                        expression: expression.trim().to_owned(),
+                    },
+                }),
+                Some(Event::Html(html) | Event::InlineHtml(html)) => inline.push(&html, false),
+                Some(Event::FootnoteReference(label)) => inline.push(&format!("[^{label}]"), false),
+                Some(Event::SoftBreak) => inline.push(" ", false),
+                Some(Event::HardBreak) => inline.push("\n", false),
+                Some(Event::Start(Tag::Strong)) => inline.bold += 1,
+                Some(Event::End(TagEnd::Strong)) => inline.bold -= 1,
+                Some(Event::Start(Tag::Emphasis)) => inline.italic += 1,
+                Some(Event::End(TagEnd::Emphasis)) => inline.italic -= 1,
+                Some(Event::Start(Tag::Strikethrough)) => inline.strikethrough += 1,
+                Some(Event::End(TagEnd::Strikethrough)) => inline.strikethrough -= 1,
+                Some(Event::Start(Tag::Link { dest_url, .. })) => {
+                    inline.links.push(dest_url.to_string())
+                }
+                Some(Event::End(TagEnd::Link)) => {
+                    inline.links.pop();
+                }
+                Some(Event::Start(Tag::Image { dest_url, .. })) => {
+                    inline.in_image = true;
+                    inline.blocks.push(BlockType::Image {
+                        image: File::External {
+                            external: ExternalFile {
+                                url: dest_url.to_string(),
+                            },
+                        },
+                    });
+                }
+                Some(Event::End(TagEnd::Image)) => inline.in_image = false,
+                _ => {}
+            }
+        }
+
+        inline.finish()
+    }
+
+    /// Collects the raw text of a code or HTML block.
+    fn text(&mut self) -> String {
+        let mut content = String::new();
+
+        while let Some(Event::Text(text) | Event::Html(text)) = self.events.peek() {
+            content.push_str(text);
+            self.events.next();
+        }
+
+        content
+    }
+}
+
+fn is_inline(event: &Event) -> bool {
+    match event {
+        Event::Text(_)
+        | Event::Code(_)
+        | Event::InlineMath(_)
+        | Event::DisplayMath(_)
+        | Event::InlineHtml(_)
+        | Event::FootnoteReference(_)
+        | Event::SoftBreak
+        | Event::HardBreak => true,
+        Event::Start(tag) => matches!(
+            tag,
+            Tag::Emphasis
+                | Tag::Strong
+                | Tag::Strikethrough
+                | Tag::Superscript
+                | Tag::Subscript
+                | Tag::Link { .. }
+                | Tag::Image { .. }
+        ),
+        Event::End(tag) => matches!(
+            tag,
+            TagEnd::Emphasis
+                | TagEnd::Strong
+                | TagEnd::Strikethrough
+                | TagEnd::Superscript
+                | TagEnd::Subscript
+                | TagEnd::Link
+                | TagEnd::Image
+        ),
+        _ => false,
+    }
+}
+
+fn text(content: &str, annotations: Annotations, link: Option<String>) -> RichText {
+    RichText::Text {
+        text: Text {
+            content: content.to_owned(),
+            link: link.clone().map(|url| Link { url }),
+        },
+        plain_text: content.to_owned(),
+        href: link,
+        annotations,
+    }
+}
+
+fn split_long_text(rich_text: Vec<RichText>) -> Vec<RichText> {
+    rich_text
+        .into_iter()
+        .flat_map(|rich_text| match rich_text {
+            RichText::Text {
+                text: Text { content, link },
+                href,
+                annotations,
+                ..
+            } if content.chars().count() > MAX_TEXT_LENGTH => content
+                .chars()
+                .collect::<Vec<_>>()
+                .chunks(MAX_TEXT_LENGTH)
+                .map(|chunk| {
+                    text(
+                        &chunk.iter().collect::<String>(),
+                        annotations.clone(),
+                        link.as_ref().map(|link| link.url.clone()).or(href.clone()),
+                    )
+                })
+                .collect(),
+            rich_text => vec![rich_text],
+        })
+        .collect()
+}
+
+fn paragraph(rich_text: Vec<RichText>, children: Vec<BlockType>) -> BlockType {
+    BlockType::Paragraph {
+        paragraph: Paragraph {
+            color: Color::Default,
+            rich_text,
+            children: wrap(children),
+        },
+    }
+}
+
+/// Turns a leading paragraph into the text of the enclosing quote or list item.
+fn take_leading_text(children: &mut Vec<BlockType>) -> Vec<RichText> {
+    match children.first() {
+        Some(BlockType::Paragraph { .. }) => match children.remove(0) {
+            BlockType::Paragraph { paragraph } => paragraph.rich_text,
+            _ => unreachable!("Expected first child to be a paragraph"),
+        },
+        _ => Vec::new(),
+    }
+}
+
+fn wrap(children: Vec<BlockType>) -> Option<Vec<Block>> {
+    if children.is_empty() {
+        None
+    } else {
+        Some(children.into_iter().map(Block::new).collect())
+    }
+}
+
+fn alert_emoji(kind: BlockQuoteKind) -> &'static str {
+    match kind {
+        BlockQuoteKind::Note => "ℹ️",
+        BlockQuoteKind::Tip => "💡",
+        BlockQuoteKind::Important => "❗",
+        BlockQuoteKind::Warning => "⚠️",
+        BlockQuoteKind::Caution => "🛑",
+    }
+}
+
+fn code_language(info: &str) -> CodeLanguage {
+    let name = info
+        .split(|character: char| character.is_whitespace() || character == ',')
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+
+    let name = match name.as_str() {
+        "" | "text" | "txt" | "plaintext" => "plain text",
+        "rs" => "rust",
+        "js" | "jsx" | "mjs" | "cjs" => "javascript",
+        "ts" | "tsx" => "typescript",
+        "py" => "python",
+        "rb" => "ruby",
+        "sh" | "zsh" | "console" => "shell",
+        "cpp" | "cc" | "cxx" | "hpp" => "c++",
+        "cs" | "csharp" => "c#",
+        "fs" | "fsharp" => "f#",
+        "objc" | "objectivec" => "objective-c",
+        "yml" => "yaml",
+        "md" => "markdown",
+        "dockerfile" => "docker",
+        "golang" => "go",
+        "kt" => "kotlin",
+        "hs" => "haskell",
+        "elixir" | "ex" | "exs" => "elixer",
+        "fortran" => "fortan",
+        "tex" => "latex",
+        "make" => "makefile",
+        "ps1" | "pwsh" => "powershell",
+        "proto" => "protobuf",
+        "vb" => "visual basic",
+        "vbnet" => "vb.net",
+        "wasm" | "wat" => "webassembly",
+        name => name,
+    };
+
+    serde_json::from_value(Value::String(name.to_owned())).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::append_json;
+    use serde_json::json;
+
+    #[test]
+    fn parses_nested_lists_and_inline_styles() {
+        let blocks = markdown_to_blocks(
+            "# Title\n\nSome **bold** and [a `link`](https://example.com).\n\n- [x] Done\n  1. Nested\n- Plain\n",
+        );
+
+        assert_eq!(
+            blocks.iter().map(append_json).collect::<Vec<_>>(),
+            vec![
+                json!({ "type": "heading_1", "heading_1": {
+                    "color": "default",
+                    "is_toggleable": false,
+                    "rich_text": [plain("Title")],
+                } }),
+                json!({ "type": "paragraph", "paragraph": {
+                    "color": "default",
+                    "rich_text": [
+                        plain("Some "),
+                        styled("bold", json!({ "bold": true }), None),
+                        plain(" and "),
+                        styled("a ", json!({}), Some("https://example.com")),
+                        styled("link", json!({ "code": true }), Some("https://example.com")),
+                        plain("."),
+                    ],
+                } }),
+                json!({ "type": "to_do", "to_do": {
+                    "color": "default",
+                    "checked": true,
+                    "rich_text": [plain("Done")],
+                    "children": [{ "type": "numbered_list_item", "numbered_list_item": {
+                        "color": "default",
+                        "rich_text": [plain("Nested")],
+                    } }],
+                } }),
+                json!({ "type": "bulleted_list_item", "bulleted_list_item": {
+                    "color": "default",
+                    "rich_text": [plain("Plain")],
+                } }),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_code_tables_images_and_math() {
+        let blocks = markdown_to_blocks(
+            "```rs\nfn main() {}\n```\n\n| a | b |\n|---|---|\n| 1 |\n\n![chart](https://example.com/chart.png)\n\n$$\nx^2\n$$\n\n> [!TIP]\n> Hint\n",
+        );
+
+        assert!(matches!(
+            &blocks[0],
+            BlockType::Code { code } if code.language == CodeLanguage::Rust
+                && crate::mapping::plain_text(&code.rich_text) == "fn main() {}"
+        ));
+        assert!(matches!(
+            &blocks[1],
+            BlockType::Table { table } if table.table_width == 2
+                && table.children.as_ref().unwrap().len() == 2
+        ));
+        assert_eq!(
+            blocks[2],
+            BlockType::Image {
+                image: File::External {
+                    external: ExternalFile {
+                        url: "https://example.com/chart.png".to_owned()
+                    }
+                }
+            }
+        );
+        assert_eq!(
+            blocks[3],
+            BlockType::Equation {
+                equation: Equation {
+                    expression: "x^2".to_owned()
+                }
+            }
+        );
+        assert!(matches!(
+            &blocks[4],
+            BlockType::Callout { callout } if callout.icon == Some(Icon::Emoji { emoji: "💡".to_owned() })
+                && crate::mapping::plain_text(&callout.rich_text) == "Hint"
+        ));
+    }
+
+    fn plain(content: &str) -> Value {
+        styled(content, json!({}), None)
+    }
+
+    fn styled(content: &str, annotations: Value, link: Option<&str>) -> Value {
+        let mut base = json!({
+            "bold": false,
+            "italic": false,
+            "strikethrough": false,
+            "underline": false,
+            "code": false,
+            "color": "default"
+        });
+        base.as_object_mut()
+            .unwrap()
+            .extend(annotations.as_object().unwrap().clone());
+
+        let mut text = json!({ "content": content });
+        let mut value = json!({
+            "type": "text",
+            "plain_text": content,
+            "annotations": base,
+        });
+        if let Some(link) = link {
+            text["link"] = json!({ "url": link });
+            value["href"] = json!(link);
+        }
+        value["text"] = text;
+
+        value
+    }
+}
@@ -0,0 +1,11 @@
+//! Conversion between block trees and CommonMark/GFM Markdown.
+
+#[cfg(feature = "convert_from_notion")]
+mod export;
+#[cfg(feature = "convert_to_notion")]
+mod import;
+
+#[cfg(feature = "convert_from_notion")]
+pub use export::{blocks_to_markdown, rich_text_to_markdown};
+#[cfg(feature = "convert_to_notion")]
+pub use import::markdown_to_blocks;
@@ -0,0 +1,201 @@
+//! The OAuth flow of public integrations.
+//!
+//! Send users to `OAuthClient::authorization_url`, exchange the `code` Notion redirects them back
+//! with for an `OAuthToken`, and build a `Client` for their workspace with
+//! `ClientBuilder::api_key` or a `TokenProvider`.
+
+use std::sync::Arc;
+
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    default_request_handler, get_http_client, BotOwner, Callback, Client, ClientBuilder, Error,
+    Result,
+};
+
+pub struct OAuthClient {
+    client_id: String,
+    client_secret: String,
+    redirect_uri: Option<String>,
+    http_client: reqwest::Client,
+    request_handler: Arc<Callback>,
+}
+
+/// The response of `/v1/oauth/token`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct OAuthToken {
+    pub access_token: String,
+    pub token_type: String,
+    /// Only issued to integrations that opted into expiring tokens.
+    pub refresh_token: Option<String>,
+    pub bot_id: String,
+    pub workspace_id: String,
+    pub workspace_name: Option<String>,
+    /// An emoji, URL or `None`.
+    pub workspace_icon: Option<String>,
+    pub owner: BotOwner,
+    pub duplicated_template_id: Option<String>,
+}
+
+impl OAuthClient {
+    pub fn new(client_id: &str, client_secret: &str) -> Result<OAuthClient> {
+        OAuthClient::from_builder(client_id, client_secret, Client::new())
+    }
+
+    /// Uses the HTTP settings (timeouts, proxy, certificates, ...), version and custom request of
+    /// `builder`, like `ClientPool::from_builder`. Its `api_key` and `token_provider` are not
+    /// used, as tokens are requested with the client secret.
+    pub fn from_builder(
+        client_id: &str,
+        client_secret: &str,
+        builder: ClientBuilder,
+    ) -> Result<OAuthClient> {
+        Ok(OAuthClient {
+            client_id: client_id.to_owned(),
+            client_secret: client_secret.to_owned(),
+            redirect_uri: None,
+            http_client: get_http_client(builder.api_version, builder.http)?,
+            request_handler: builder
+                .custom_request
+                .unwrap_or_else(default_request_handler),
+        })
+    }
+
+    /// Required if the integration has more than one redirect URI.
+    pub fn redirect_uri(mut self, redirect_uri: &str) -> Self {
+        self.redirect_uri = Some(redirect_uri.to_owned());
+
+        self
+    }
+
+    /// The URL that asks users to add the integration to one of their workspaces. `state` is
+    /// passed back to the redirect URI as is.
+    pub fn authorization_url(&self, state: Option<&str>) -> Result<String> {
+        let mut parameters = vec![
+            ("client_id", self.client_id.as_str()),
+            ("response_type", "code"),
+            ("owner", "user"),
+        ];
+        if let Some(redirect_uri) = &self.redirect_uri {
+            parameters.push(("redirect_uri", redirect_uri));
+        }
+        if let Some(state) = state {
+            parameters.push(("state", state));
+        }
+
+        let url = Url::parse_with_params("https://api.notion.com/v1/oauth/authorize", parameters)
+            .map_err(|error| Error::InvalidUrl(error.to_string()))?;
+
+        Ok(url.to_string())
+    }
+
+    /// Exchanges the `code` of the redirect for an access token.
+    pub async fn exchange_code(&self, code: &str) -> Result<OAuthToken> {
+        let mut body = json!({ "grant_type": "authorization_code", "code": code });
+        if let Some(redirect_uri) = &self.redirect_uri {
+            body["redirect_uri"] = json!(redirect_uri);
+        }
+
+        self.token(body).await
+    }
+
+    /// Trades a refresh token for a new access (and refresh) token.
+    pub async fn refresh(&self, refresh_token: &str) -> Result<OAuthToken> {
+        self.token(json!({ "grant_type": "refresh_token", "refresh_token": refresh_token }))
+            .await
+    }
+
+    async fn token(&self, body: Value) -> Result<OAuthToken> {
+        let mut request = self
+            .http_client
+            .post("https://api.notion.com/v1/oauth/token")
+            .basic_auth(&self.client_id, Some(&self.client_secret))
+            .json(&body);
+
+        let response = (self.request_handler)(&mut request).await?;
+
+        match response.error_for_status_ref() {
+            Ok(_) => Ok(response.json().await?),
+            Err(error) => {
+                let body = response.json::<Value>().await?;
+                Err(Error::Http(error, Some(body)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_authorization_url() {
+        let client = OAuthClient::new("463558a3-725e-4f37-b6d3-0889894f68de", "secret")
+            .unwrap()
+            .redirect_uri("https://example.com/auth/notion/callback");
+
+        assert_eq!(
+            client.authorization_url(Some("a b")).unwrap(),
+            "https://api.notion.com/v1/oauth/authorize?client_id=463558a3-725e-4f37-b6d3-0889894f68de&response_type=code&owner=user&redirect_uri=https%3A%2F%2Fexample.com%2Fauth%2Fnotion%2Fcallback&state=a+b"
+        );
+    }
+
+    #[test]
+    fn parses_token_response() {
+        let token = serde_json::from_value::<OAuthToken>(json!({
+            "access_token": "e202e8c9-0990-40af-855f-ff8f872b1ec6c",
+            "bot_id": "b3414d659-1224-5ty7-6ffr-cc9d8773drt601288f",
+            "duplicated_template_id": null,
+            "owner": {
+                "type": "user",
+                "user": { "object": "user", "id": "2d5e7f4a-1c55-4ba0-9cfa-3f2c29b6ba10" }
+            },
+            "workspace_icon": "https://website.domain/images/image.png",
+            "workspace_id": "j565j4d7x3-2882-61bs-564a-jj9d9ui-c36hxfr7x",
+            "workspace_name": "Ada's Notion Workspace",
+            "token_type": "bearer"
+        }))
+        .unwrap();
+
+        assert_eq!(token.refresh_token, None);
+        assert!(matches!(token.owner, BotOwner::User { .. }));
+    }
+
+    #[tokio::test]
+    async fn tokens_are_requested_with_the_settings_of_the_builder() {
+        let client = OAuthClient::from_builder(
+            "client",
+            "secret",
+            Client::new().custom_request(|request| {
+                Box::pin(async move {
+                    let request = request.try_clone().unwrap().build()?;
+                    assert_eq!(request.url().path(), "/v1/oauth/token");
+                    assert!(request.headers()["Authorization"]
+                        .to_str()
+                        .unwrap()
+                        .starts_with("Basic "));
+                    let body = json!({
+                        "access_token": "token",
+                        "token_type": "bearer",
+                        "bot_id": "bot",
+                        "workspace_id": "workspace",
+                        "workspace_name": null,
+                        "workspace_icon": null,
+                        "owner": { "type": "workspace", "workspace": true },
+                        "duplicated_template_id": null
+                    });
+
+                    Ok(reqwest::Response::from(http::Response::new(
+                        body.to_string(),
+                    )))
+                })
+            }),
+        )
+        .unwrap();
+
+        let token = client.exchange_code("code").await.unwrap();
+        assert_eq!(token.access_token, "token");
+    }
+}
@@ -0,0 +1,382 @@
+//! Updates a block tree in place to match new content, keeping the blocks (and with them their
+//! comments and the links to them) that stay the same.
+
+use std::mem::discriminant;
+
+use serde_json::Value;
+
+use crate::{append_json, Block, BlockId, BlockType};
+#[cfg(feature = "request")]
+use crate::{BlockChildrenAppendOptions, BlockOptions, BlockUpdateOptions, Blocks, Result};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockOperation {
+    /// Replaces the content of a block with content of the same type.
+    Update {
+        block_id: BlockId,
+        block: BlockType,
+    },
+    /// Inserts blocks, with their children, after the child `after` of `parent_id`, or at the end.
+    Append {
+        parent_id: BlockId,
+        after: Option<BlockId>,
+        children: Vec<BlockType>,
+    },
+    Delete {
+        block_id: BlockId,
+    },
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BlockPatch {
+    pub operations: Vec<BlockOperation>,
+    /// Blocks that new blocks belong in front of, but that can't be moved behind them, so the new
+    /// blocks are inserted after them instead.
+    pub misordered: Vec<BlockId>,
+}
+
+/// Returns the operations that turn the children `current` of `parent_id` into `desired`.
+///
+/// `current` has to be fetched with `Blocks::fetch_tree`, descendants that weren't fetched are left
+/// alone. So are child pages, child databases, synced blocks and other blocks that can't be
+/// recreated from their `BlockType`.
+///
+/// Blocks are matched along the longest run of unchanged blocks, and changed blocks in between are
+/// updated if their type stayed the same. Notion can only insert blocks after a block, so blocks
+/// inserted before the first remaining block are appended after it instead of deleting and
+/// recreating it, see `BlockPatch::misordered`.
+pub fn diff_blocks(parent_id: &BlockId, current: &[Block], desired: &[BlockType]) -> BlockPatch {
+    let mut patch = BlockPatch::default();
+    diff_children(
+        parent_id,
+        current,
+        &desired.iter().collect::<Vec<_>>(),
+        &mut patch,
+    );
+
+    patch
+}
+
+enum Step<'a> {
+    Keep(&'a Block, &'a BlockType),
+    Update(&'a Block, &'a BlockType),
+    Delete(&'a Block),
+    Insert(&'a BlockType),
+}
+
+fn diff_children(
+    parent_id: &BlockId,
+    current: &[Block],
+    desired: &[&BlockType],
+    patch: &mut BlockPatch,
+) {
+    let current = current
+        .iter()
+        .filter(|block| is_editable(&block.block))
+        .collect::<Vec<_>>();
+    let current_content = current
+        .iter()
+        .map(|block| content(&block.block))
+        .collect::<Vec<_>>();
+    let desired_content = desired
+        .iter()
+        .map(|block| content(block))
+        .collect::<Vec<_>>();
+
+    // lengths[i][j] is the length of the longest common subsequence of current[i..] and desired[j..]
+    let mut lengths = vec![vec![0usize; desired.len() + 1]; current.len() + 1];
+    for i in (0..current.len()).rev() {
+        for j in (0..desired.len()).rev() {
+            lengths[i][j] = if current_content[i] == desired_content[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut steps = Vec::new();
+    let (mut removed, mut added) = (Vec::new(), Vec::new());
+    let (mut i, mut j) = (0, 0);
+    while i < current.len() || j < desired.len() {
+        if i < current.len() && j < desired.len() && current_content[i] == desired_content[j] {
+            align_gap(&removed, &added, &mut steps);
+            (removed, added) = (Vec::new(), Vec::new());
+
+            steps.push(Step::Keep(current[i], desired[j]));
+            i += 1;
+            j += 1;
+        } else if j == desired.len()
+            || (i < current.len() && lengths[i + 1][j] >= lengths[i][j + 1])
+        {
+            removed.push(current[i]);
+            i += 1;
+        } else {
+            added.push(desired[j]);
+            j += 1;
+        }
+    }
+    align_gap(&removed, &added, &mut steps);
+
+    let mut anchor: Option<&Block> = None;
+    let mut inserted = Vec::new();
+    for step in steps {
+        match step {
+            Step::Insert(block) => inserted.push(block.clone()),
+            Step::Delete(block) => patch.operations.push(BlockOperation::Delete {
+                block_id: BlockId::new_unchecked(block.id.as_str()),
+            }),
+            Step::Keep(block, desired) | Step::Update(block, desired) => {
+                if anchor.is_none() && !inserted.is_empty() {
+                    // There is nothing to insert the blocks after, so they go after this block
+                    patch
+                        .misordered
+                        .push(BlockId::new_unchecked(block.id.as_str()));
+                } else {
+                    append(parent_id, anchor, &mut inserted, patch);
+                }
+
+                if content(&block.block) != content(desired) {
+                    patch.operations.push(BlockOperation::Update {
+                        block_id: BlockId::new_unchecked(block.id.as_str()),
+                        block: desired.clone(),
+                    });
+                }
+                diff_descendants(block, desired, patch);
+                anchor = Some(block);
+            }
+        }
+    }
+    append(parent_id, anchor, &mut inserted, patch);
+}
+
+/// Pairs up the blocks between two unchanged blocks, updating blocks whose type stayed the same.
+fn align_gap<'a>(removed: &[&'a Block], added: &[&'a BlockType], steps: &mut Vec<Step<'a>>) {
+    let (mut i, mut j) = (0, 0);
+    while i < removed.len() && j < added.len() {
+        let same_type =
+            |block: &&BlockType| discriminant(*block) == discriminant(&removed[i].block);
+
+        if same_type(&added[j]) {
+            steps.push(Step::Update(removed[i], added[j]));
+            i += 1;
+            j += 1;
+        } else if added[j..].iter().any(same_type) {
+            steps.push(Step::Insert(added[j]));
+            j += 1;
+        } else {
+            steps.push(Step::Delete(removed[i]));
+            i += 1;
+        }
+    }
+    steps.extend(removed[i..].iter().map(|block| Step::Delete(block)));
+    steps.extend(added[j..].iter().map(|block| Step::Insert(block)));
+}
+
+fn diff_descendants(block: &Block, desired: &BlockType, patch: &mut BlockPatch) {
+    let children = match block.block.children() {
+        Some(children) => children.as_slice(),
+        None if !block.has_children => &[],
+        // Not fetched
+        None => return,
+    };
+    let desired = desired
+        .children()
+        .map(|children| {
+            children
+                .iter()
+                .map(|child| &child.block)
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    diff_children(
+        &BlockId::new_unchecked(block.id.as_str()),
+        children,
+        &desired,
+        patch,
+    );
+}
+
+fn append(
+    parent_id: &BlockId,
+    after: Option<&Block>,
+    inserted: &mut Vec<BlockType>,
+    patch: &mut BlockPatch,
+) {
+    if !inserted.is_empty() {
+        patch.operations.push(BlockOperation::Append {
+            parent_id: parent_id.clone(),
+            after: after.map(|block| BlockId::new_unchecked(block.id.as_str())),
+            children: std::mem::take(inserted),
+        });
+    }
+}
+
+/// What the API accepts for a block, without its children.
+fn content(block: &BlockType) -> Value {
+    let mut block = block.clone();
+    if let Some(children) = block.children_mut() {
+        *children = None;
+    }
+
+    append_json(&block)
+}
+
+fn is_editable(block: &BlockType) -> bool {
+    !matches!(
+        block,
+        BlockType::ChildPage { .. }
+            | BlockType::ChildDatabase { .. }
+            | BlockType::SyncedBlock
+            | BlockType::LinkToPage
+            | BlockType::Template
+            | BlockType::Unsupported
+    )
+}
+
+#[cfg(feature = "request")]
+impl Blocks {
+    /// Applies the operations of a `BlockPatch` in order.
+    pub async fn apply(&self, operations: Vec<BlockOperation>) -> Result<()> {
+        for operation in operations {
+            match operation {
+                BlockOperation::Update { block_id, block } => {
+                    self.update(BlockUpdateOptions { block_id, block }).await?;
+                }
+                BlockOperation::Append {
+                    parent_id,
+                    after,
+                    children,
+                } => {
+                    self.children()
+                        .append(BlockChildrenAppendOptions {
+                            block_id: parent_id,
+                            children,
+                            after,
+                        })
+                        .await?;
+                }
+                BlockOperation::Delete { block_id } => {
+                    self.delete(BlockOptions { block_id }).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn block_type(kind: &str, text: &str) -> BlockType {
+        serde_json::from_value(json!({
+            "type": kind,
+            kind: {
+                "color": "default",
+                "is_toggleable": false,
+                "rich_text": [{
+                    "type": "text",
+                    "text": { "content": text, "link": null },
+                    "annotations": {
+                        "bold": false, "italic": false, "strikethrough": false,
+                        "underline": false, "code": false, "color": "default"
+                    },
+                    "plain_text": text,
+                    "href": null
+                }]
+            }
+        }))
+        .unwrap()
+    }
+
+    fn block(id: &str, kind: &str, text: &str) -> Block {
+        Block {
+            id: id.to_owned(),
+            ..Block::new(block_type(kind, text))
+        }
+    }
+
+    #[test]
+    fn unchanged_blocks_are_kept() {
+        let parent: BlockId = BlockId::new_unchecked("page");
+        let current = [
+            block("a", "heading_1", "Overview"),
+            block("b", "paragraph", "Old"),
+            block("c", "paragraph", "Same"),
+            block("d", "bulleted_list_item", "Gone"),
+        ];
+        let desired = [
+            block_type("heading_1", "Overview"),
+            block_type("paragraph", "New"),
+            block_type("paragraph", "Same"),
+            BlockType::Divider,
+        ];
+
+        assert_eq!(
+            diff_blocks(&parent, &current, &desired).operations,
+            [
+                BlockOperation::Update {
+                    block_id: BlockId::new_unchecked("b"),
+                    block: desired[1].clone()
+                },
+                BlockOperation::Delete {
+                    block_id: BlockId::new_unchecked("d")
+                },
+                BlockOperation::Append {
+                    parent_id: parent.clone(),
+                    after: Some(BlockId::new_unchecked("c")),
+                    children: vec![BlockType::Divider]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn blocks_inserted_before_the_first_block_go_after_it() {
+        let parent: BlockId = BlockId::new_unchecked("page");
+        let desired = [block_type("heading_2", "New"), block_type("toggle", "Same")];
+        let inserted_after_toggle = BlockPatch {
+            operations: vec![BlockOperation::Append {
+                parent_id: parent.clone(),
+                after: Some(BlockId::new_unchecked("a")),
+                children: vec![desired[0].clone()],
+            }],
+            misordered: vec![BlockId::new_unchecked("a")],
+        };
+
+        // Nothing is deleted to recreate the block behind the new ones
+        assert_eq!(
+            diff_blocks(&parent, &[block("a", "toggle", "Same")], &desired),
+            inserted_after_toggle
+        );
+
+        // A toggle with a child page, which would go to the trash with it
+        let mut toggle = block("a", "toggle", "Same");
+        *toggle.block.children_mut().unwrap() = Some(vec![Block {
+            id: "child".to_owned(),
+            ..Block::new(BlockType::ChildPage {
+                child_page: crate::ChildPage {
+                    title: "Notes".to_owned(),
+                    children: None,
+                },
+            })
+        }]);
+        toggle.has_children = true;
+        assert_eq!(
+            diff_blocks(&parent, &[toggle], &desired),
+            inserted_after_toggle
+        );
+
+        // A toggle whose children weren't fetched
+        let mut toggle = block("a", "toggle", "Same");
+        toggle.has_children = true;
+        assert_eq!(
+            diff_blocks(&parent, &[toggle], &desired),
+            inserted_after_toggle
+        );
+    }
+}
@@ -0,0 +1,342 @@
+//! Clients for many workspaces sharing a single connection pool.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use reqwest::StatusCode;
+
+use crate::{
+    default_request_handler, get_http_client, with_token_provider, ApiVersion, Callback, Client,
+    ClientBuilder, DeserializationMode, Result,
+};
+
+/// Hands out a `Client` per workspace (or bot, or any other key), all of them sharing one
+/// connection pool, with a separate rate limit budget for every token.
+///
+/// Entries that haven't made a request for `idle_timeout` are evicted when the next client is
+/// handed out, or with `evict_idle`.
+pub struct ClientPool {
+    http_client: Arc<reqwest::Client>,
+    request_handler: Arc<Callback>,
+    deserialization_mode: DeserializationMode,
+    api_version: ApiVersion,
+    requests_per_second: f64,
+    idle_timeout: Duration,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+struct Entry {
+    token: String,
+    client: Client,
+    limiter: Arc<RateLimiter>,
+    last_used: Arc<Mutex<Instant>>,
+}
+
+impl ClientPool {
+    pub fn new() -> Result<ClientPool> {
+        ClientPool::from_builder(Client::new())
+    }
+
+    /// Shares the HTTP settings (timeouts, proxy, certificates, ...), version, deserialization
+    /// mode and custom request of `builder` among all clients. Its `api_key` and
+    /// `token_provider` are not used, as every client has a token of its own.
+    ///
+    /// The `Notion-Version` is part of the shared HTTP client, so it is set with
+    /// `ClientBuilder::notion_version` on `builder` rather than on the pool.
+    pub fn from_builder(builder: ClientBuilder) -> Result<ClientPool> {
+        Ok(ClientPool {
+            http_client: Arc::new(get_http_client(builder.api_version, builder.http)?),
+            request_handler: builder
+                .custom_request
+                .unwrap_or_else(default_request_handler),
+            deserialization_mode: builder.deserialization_mode,
+            api_version: builder.api_version,
+            // Notion allows an average of three requests per second for each integration
+            requests_per_second: 3.0,
+            idle_timeout: Duration::from_secs(15 * 60),
+            entries: Mutex::default(),
+        })
+    }
+
+    pub fn requests_per_second(mut self, requests_per_second: f64) -> Self {
+        self.requests_per_second = requests_per_second;
+
+        self
+    }
+
+    pub fn idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+
+        self
+    }
+
+    pub fn deserialization_mode(mut self, mode: DeserializationMode) -> Self {
+        self.deserialization_mode = mode;
+
+        self
+    }
+
+    /// See `ClientBuilder::custom_request`.
+    pub fn custom_request<F>(mut self, callback: F) -> Self
+    where
+        for<'c> F: Fn(
+                &'c mut reqwest::RequestBuilder,
+            ) -> futures_core::future::BoxFuture<
+                'c,
+                std::result::Result<reqwest::Response, reqwest::Error>,
+            >
+            + 'static
+            + Send
+            + Sync,
+    {
+        self.request_handler = Arc::new(callback);
+
+        self
+    }
+
+    /// Returns the client of `key`, creating it if there is none yet or if it was created with
+    /// a different token.
+    pub fn client(&self, key: &str, token: &str) -> Client {
+        let mut entries = self
+            .entries
+            .lock()
+            .expect("client pool lock to not be poisoned");
+        self.evict(&mut entries);
+
+        match entries.get(key) {
+            Some(entry) if entry.token == token => entry.client.clone(),
+            _ => {
+                // Keys sharing a token share its budget too
+                let limiter = entries
+                    .values()
+                    .find(|entry| entry.token == token)
+                    .map(|entry| entry.limiter.clone())
+                    .unwrap_or_else(|| Arc::new(RateLimiter::new(self.requests_per_second)));
+                let entry = self.entry(token, limiter);
+                let client = entry.client.clone();
+                entries.insert(key.to_owned(), entry);
+
+                client
+            }
+        }
+    }
+
+    /// Returns the client of `key` if there is one.
+    pub fn get(&self, key: &str) -> Option<Client> {
+        let entries = self
+            .entries
+            .lock()
+            .expect("client pool lock to not be poisoned");
+
+        entries.get(key).map(|entry| entry.client.clone())
+    }
+
+    /// Removes the client of `key`, e.g. after its token was revoked.
+    pub fn remove(&self, key: &str) -> Option<Client> {
+        let mut entries = self
+            .entries
+            .lock()
+            .expect("client pool lock to not be poisoned");
+
+        entries.remove(key).map(|entry| entry.client)
+    }
+
+    /// Removes clients that haven't made a request for `idle_timeout` and returns how many.
+    pub fn evict_idle(&self) -> usize {
+        let mut entries = self
+            .entries
+            .lock()
+            .expect("client pool lock to not be poisoned");
+
+        self.evict(&mut entries)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .expect("client pool lock to not be poisoned")
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn evict(&self, entries: &mut HashMap<String, Entry>) -> usize {
+        let before = entries.len();
+        entries.retain(|_, entry| {
+            entry
+                .last_used
+                .lock()
+                .expect("client pool lock to not be poisoned")
+                .elapsed()
+                < self.idle_timeout
+        });
+
+        before - entries.len()
+    }
+
+    fn entry(&self, token: &str, limiter: Arc<RateLimiter>) -> Entry {
+        let last_used = Arc::new(Mutex::new(Instant::now()));
+        let request_handler = self.request_handler.clone();
+
+        let limited: Arc<Callback> = {
+            let last_used = last_used.clone();
+            let limiter = limiter.clone();
+
+            Arc::new(move |request_builder: &mut reqwest::RequestBuilder| {
+                let last_used = last_used.clone();
+                let limiter = limiter.clone();
+                let request_handler = request_handler.clone();
+
+                Box::pin(async move {
+                    *last_used
+                        .lock()
+                        .expect("client pool lock to not be poisoned") = Instant::now();
+                    limiter.acquire().await;
+
+                    let response = request_handler(request_builder).await?;
+
+                    if response.status() == StatusCode::TOO_MANY_REQUESTS {
+                        let retry_after = response
+                            .headers()
+                            .get("Retry-After")
+                            .and_then(|value| value.to_str().ok()?.parse::<u64>().ok())
+                            .unwrap_or(1);
+                        limiter.pause(Duration::from_secs(retry_after));
+                    }
+
+                    Ok(response)
+                })
+            })
+        };
+
+        Entry {
+            token: token.to_owned(),
+            client: Client::from_parts(
+                self.http_client.clone(),
+                with_token_provider(Arc::new(token.to_owned()), limited),
+                self.deserialization_mode,
+                self.api_version,
+            ),
+            limiter,
+            last_used,
+        }
+    }
+}
+
+/// Spaces requests evenly, so that bursts are spread out instead of running into 429s.
+struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    fn new(requests_per_second: f64) -> RateLimiter {
+        RateLimiter {
+            interval: Duration::from_secs_f64(1.0 / requests_per_second.max(f64::EPSILON)),
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Reserves the next free slot and waits for it.
+    async fn acquire(&self) {
+        let wait = {
+            let mut next = self
+                .next
+                .lock()
+                .expect("rate limiter lock to not be poisoned");
+            let now = Instant::now();
+            let slot = (*next).max(now);
+            *next = slot + self.interval;
+
+            slot - now
+        };
+
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Holds off all requests for `duration`, after Notion asked to retry later.
+    fn pause(&self, duration: Duration) {
+        let mut next = self
+            .next
+            .lock()
+            .expect("rate limiter lock to not be poisoned");
+        *next = (*next).max(Instant::now() + duration);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+    use serde_json::json;
+
+    fn pool() -> ClientPool {
+        ClientPool::new()
+            .unwrap()
+            .requests_per_second(1000.0)
+            .custom_request(|request| {
+                Box::pin(async move {
+                    let request = request.try_clone().unwrap().build()?;
+                    let authorization = request.headers()["Authorization"].to_str().unwrap();
+                    let body =
+                        json!({ "object": "user", "id": authorization, "type": "bot", "bot": {} });
+
+                    Ok(reqwest::Response::from(http::Response::new(
+                        body.to_string(),
+                    )))
+                })
+            })
+    }
+
+    #[tokio::test]
+    async fn clients_use_their_own_token() {
+        let pool = pool();
+
+        let first = pool.client("workspace-a", "token-a");
+        let second = pool.client("workspace-b", "token-b");
+
+        assert_eq!(first.users.me().await.unwrap().id(), "Bearer token-a");
+        assert_eq!(second.users.me().await.unwrap().id(), "Bearer token-b");
+        assert_eq!(pool.len(), 2);
+
+        let replaced = pool.client("workspace-a", "token-c");
+        assert_eq!(replaced.users.me().await.unwrap().id(), "Bearer token-c");
+        assert_eq!(pool.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn clients_share_the_settings_of_the_builder() {
+        // A proxy that accepts connections into its backlog, but never answers
+        let proxy = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = proxy.local_addr().unwrap();
+
+        let pool = ClientPool::from_builder(
+            Client::new()
+                .proxy(reqwest::Proxy::all(format!("http://{address}")).unwrap())
+                .timeout(Duration::from_millis(100)),
+        )
+        .unwrap();
+
+        let error = pool
+            .client("workspace-a", "token-a")
+            .users
+            .me()
+            .await
+            .unwrap_err();
+        assert!(matches!(error, Error::Http(error, None) if error.is_timeout()));
+    }
+
+    #[test]
+    fn idle_clients_are_evicted() {
+        let pool = pool().idle_timeout(Duration::ZERO);
+
+        pool.client("workspace-a", "token-a");
+        assert_eq!(pool.evict_idle(), 1);
+        assert!(pool.get("workspace-a").is_none());
+    }
+}
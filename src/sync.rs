@@ -0,0 +1,609 @@
+//! Mirrors Notion databases into SQLite tables, fetching only the pages edited since the last run.
+//!
+//! Every row has the columns `_id`, `_url`, `_created_time`, `_last_edited_time` and `_archived`,
+//! followed by a column per property of the database. A property keeps the column it was given
+//! first, even when renamed. New properties whose name is taken by one of those, or by another
+//! column that only differs in case, get a number appended, e.g. `_id_2`.
+
+use std::collections::{HashMap, HashSet};
+
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use serde_json::json;
+
+use crate::mapping::plain_text;
+use crate::{
+    Client, DatabaseId, DatabaseOptions, DatabaseProperty, DatabaseQueryOptions, Formula, Page,
+    Property, Result,
+};
+
+pub struct DatabaseSync {
+    client: Client,
+    connection: Connection,
+}
+
+pub struct SyncOptions<'a> {
+    pub database_id: DatabaseId,
+    /// Created from the schema of the database, and extended when properties are added.
+    pub table: &'a str,
+    /// Fetches every page instead of those edited since the last run, and marks the rows of pages
+    /// that are gone as archived. Queries leave archived pages out, so only full runs notice them.
+    pub full: bool,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SyncReport {
+    pub upserted: usize,
+    pub archived: usize,
+    /// The `last_edited_time` of the most recently edited page seen so far.
+    pub watermark: Option<String>,
+}
+
+impl DatabaseSync {
+    /// Stores the sync state of every table in `notion_sync_state` of `connection`, and the
+    /// column of every property in `notion_sync_columns`.
+    pub fn new(client: Client, connection: Connection) -> Result<DatabaseSync> {
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS notion_sync_state (
+                database_id TEXT NOT NULL,
+                table_name TEXT NOT NULL,
+                watermark TEXT,
+                PRIMARY KEY (database_id, table_name)
+            );
+            CREATE TABLE IF NOT EXISTS notion_sync_columns (
+                database_id TEXT NOT NULL,
+                table_name TEXT NOT NULL,
+                property_id TEXT NOT NULL,
+                column_name TEXT NOT NULL,
+                PRIMARY KEY (database_id, table_name, property_id)
+            );",
+        )?;
+
+        Ok(DatabaseSync { client, connection })
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    pub fn into_connection(self) -> Connection {
+        self.connection
+    }
+
+    pub fn watermark(&self, database_id: &DatabaseId, table: &str) -> Result<Option<String>> {
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT watermark FROM notion_sync_state WHERE database_id = ?1 AND table_name = ?2",
+                params![database_id.as_str(), table],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten())
+    }
+
+    /// Upserts the pages edited since the last run, oldest first.
+    ///
+    /// Every page of query results is written in one transaction together with the new
+    /// watermark, so a run that fails halfway resumes after the last written page. Pages edited
+    /// in the minute of the watermark are fetched again, as Notion rounds `last_edited_time` to
+    /// minutes.
+    pub async fn sync(&mut self, options: SyncOptions<'_>) -> Result<SyncReport> {
+        let database = self
+            .client
+            .databases
+            .retrieve(DatabaseOptions {
+                database_id: options.database_id.clone(),
+            })
+            .await?;
+        let columns =
+            self.create_table(&options.database_id, options.table, &database.properties)?;
+
+        let mut report = SyncReport {
+            watermark: self.watermark(&options.database_id, options.table)?,
+            ..Default::default()
+        };
+        let filter = match &report.watermark {
+            Some(watermark) if !options.full => Some(json!({
+                "timestamp": "last_edited_time",
+                "last_edited_time": { "on_or_after": watermark }
+            })),
+            _ => None,
+        };
+
+        let mut seen = HashSet::new();
+        let mut start_cursor = None;
+        loop {
+            let results = self
+                .client
+                .databases
+                .query(DatabaseQueryOptions {
+                    database_id: options.database_id.clone(),
+                    filter: filter.clone(),
+                    sorts: Some(json!([
+                        { "timestamp": "last_edited_time", "direction": "ascending" }
+                    ])),
+                    start_cursor,
+                })
+                .await?;
+
+            let transaction = self.connection.transaction()?;
+            for page in &results.results {
+                upsert(&transaction, options.table, &columns, page)?;
+                seen.insert(page.id.clone());
+                report.upserted += 1;
+            }
+            if let Some(page) = results.results.last() {
+                let watermark = page.last_edited_time.to_string();
+                transaction.execute(
+                    "INSERT INTO notion_sync_state (database_id, table_name, watermark)
+                    VALUES (?1, ?2, ?3)
+                    ON CONFLICT (database_id, table_name) DO UPDATE SET watermark = excluded.watermark",
+                    params![options.database_id.as_str(), options.table, watermark],
+                )?;
+                report.watermark = Some(watermark);
+            }
+            transaction.commit()?;
+
+            match results.next_cursor {
+                Some(next_cursor) if results.has_more => start_cursor = Some(next_cursor),
+                _ => break,
+            }
+        }
+
+        if options.full {
+            let transaction = self.connection.transaction()?;
+            let ids = {
+                let mut statement = transaction.prepare(&format!(
+                    "SELECT _id FROM {} WHERE _archived = 0",
+                    quote(options.table)
+                ))?;
+                let ids = statement
+                    .query_map([], |row| row.get::<_, String>(0))?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                ids
+            };
+            for id in ids.iter().filter(|id| !seen.contains(*id)) {
+                transaction.execute(
+                    &format!(
+                        "UPDATE {} SET _archived = 1 WHERE _id = ?1",
+                        quote(options.table)
+                    ),
+                    [id],
+                )?;
+                report.archived += 1;
+            }
+            transaction.commit()?;
+        }
+
+        Ok(report)
+    }
+
+    /// Creates the table and a column for every property that has none yet, and returns the
+    /// column of each property id.
+    fn create_table(
+        &mut self,
+        database_id: &DatabaseId,
+        table: &str,
+        properties: &HashMap<String, DatabaseProperty>,
+    ) -> Result<HashMap<String, String>> {
+        let transaction = self.connection.transaction()?;
+        transaction.execute(
+            &format!(
+                "CREATE TABLE IF NOT EXISTS {} (
+                    _id TEXT PRIMARY KEY,
+                    _url TEXT NOT NULL,
+                    _created_time TEXT NOT NULL,
+                    _last_edited_time TEXT NOT NULL,
+                    _archived INTEGER NOT NULL DEFAULT 0
+                )",
+                quote(table)
+            ),
+            [],
+        )?;
+
+        // SQLite ignores the ASCII case of column names
+        let mut taken = {
+            let mut statement =
+                transaction.prepare(&format!("PRAGMA table_info({})", quote(table)))?;
+            let taken = statement
+                .query_map([], |row| row.get::<_, String>(1))?
+                .map(|column| column.map(|column| column.to_ascii_lowercase()))
+                .collect::<rusqlite::Result<HashSet<_>>>()?;
+            taken
+        };
+        let mut columns = {
+            let mut statement = transaction.prepare(
+                "SELECT property_id, column_name FROM notion_sync_columns
+                WHERE database_id = ?1 AND table_name = ?2",
+            )?;
+            let columns = statement
+                .query_map(params![database_id.as_str(), table], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+                })?
+                .collect::<rusqlite::Result<HashMap<_, _>>>()?;
+            columns
+        };
+
+        let mut names = properties.keys().collect::<Vec<_>>();
+        names.sort();
+        for name in names {
+            let Some(id) = properties[name].id() else {
+                continue;
+            };
+            if columns.contains_key(&id) {
+                continue;
+            }
+
+            let mut column = name.clone();
+            let mut number = 1;
+            while !taken.insert(column.to_ascii_lowercase()) {
+                number += 1;
+                column = format!("{name}_{number}");
+            }
+
+            let column_type = match properties[name] {
+                DatabaseProperty::Number { .. } => "REAL",
+                DatabaseProperty::Checkbox { .. } => "INTEGER",
+                _ => "TEXT",
+            };
+            transaction.execute(
+                &format!(
+                    "ALTER TABLE {} ADD COLUMN {} {column_type}",
+                    quote(table),
+                    quote(&column)
+                ),
+                [],
+            )?;
+            transaction.execute(
+                "INSERT INTO notion_sync_columns (database_id, table_name, property_id, column_name)
+                VALUES (?1, ?2, ?3, ?4)",
+                params![database_id.as_str(), table, id, column],
+            )?;
+            columns.insert(id, column);
+        }
+        transaction.commit()?;
+
+        Ok(columns)
+    }
+}
+
+fn upsert(
+    connection: &Connection,
+    table: &str,
+    columns: &HashMap<String, String>,
+    page: &Page,
+) -> Result<()> {
+    let archived = page.archived || page.in_trash == Some(true);
+    let mut values = vec![
+        ("_id".to_owned(), SqlValue::Text(page.id.clone())),
+        ("_url".to_owned(), SqlValue::Text(page.url.clone())),
+        (
+            "_created_time".to_owned(),
+            SqlValue::Text(page.created_time.to_string()),
+        ),
+        (
+            "_last_edited_time".to_owned(),
+            SqlValue::Text(page.last_edited_time.to_string()),
+        ),
+        ("_archived".to_owned(), SqlValue::Integer(archived as i64)),
+    ];
+    for property in page.properties.values() {
+        if let Some(column) = property.id().and_then(|id| columns.get(&id)) {
+            values.push((column.clone(), sql_value(property)));
+        }
+    }
+
+    let names = values
+        .iter()
+        .map(|(name, _)| quote(name))
+        .collect::<Vec<_>>();
+    let placeholders = (1..=values.len())
+        .map(|index| format!("?{index}"))
+        .collect::<Vec<_>>();
+    let updates = names
+        .iter()
+        .skip(1)
+        .map(|name| format!("{name} = excluded.{name}"))
+        .collect::<Vec<_>>();
+
+    connection.execute(
+        &format!(
+            "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT (_id) DO UPDATE SET {}",
+            quote(table),
+            names.join(", "),
+            placeholders.join(", "),
+            updates.join(", ")
+        ),
+        params_from_iter(values.into_iter().map(|(_, value)| value)),
+    )?;
+
+    Ok(())
+}
+
+/// Flattens a property into a column value, with lists stored as JSON arrays.
+fn sql_value(property: &Property) -> SqlValue {
+    let text = |value: Option<&String>| {
+        value.map_or(SqlValue::Null, |value| SqlValue::Text(value.clone()))
+    };
+    let list = |values: Vec<&String>| SqlValue::Text(json!(values).to_string());
+
+    match property {
+        Property::Checkbox { checkbox, .. } => SqlValue::Integer(*checkbox as i64),
+        Property::CreatedTime { created_time, .. } => SqlValue::Text(created_time.to_string()),
+        Property::LastEditedTime {
+            last_edited_time, ..
+        } => SqlValue::Text(last_edited_time.to_string()),
+        Property::Date { date, .. } => date.as_ref().map_or(SqlValue::Null, |date| {
+            SqlValue::Text(date.start.to_string())
+        }),
+        Property::Email { email: value, .. }
+        | Property::PhoneNumber {
+            phone_number: value,
+            ..
+        }
+        | Property::Url { url: value, .. } => text(value.as_ref()),
+        Property::Files { files, .. } => SqlValue::Text(
+            json!(files.iter().map(|file| file.name()).collect::<Vec<_>>()).to_string(),
+        ),
+        Property::Formula { formula, .. } => match formula {
+            Formula::Boolean { boolean } => {
+                boolean.map_or(SqlValue::Null, |boolean| SqlValue::Integer(boolean as i64))
+            }
+            Formula::Date { date } => date.as_ref().map_or(SqlValue::Null, |date| {
+                SqlValue::Text(date.start.to_string())
+            }),
+            Formula::Number { number } => {
+                number.map_or(SqlValue::Null, |number| SqlValue::Real(number as f64))
+            }
+            Formula::String { string } => text(string.as_ref()),
+        },
+        Property::Select { select: option, .. } | Property::Status { status: option, .. } => {
+            text(option.as_ref().map(|option| &option.name))
+        }
+        Property::MultiSelect { multi_select, .. } => {
+            list(multi_select.iter().map(|option| &option.name).collect())
+        }
+        Property::Number { number, .. } => {
+            number.map_or(SqlValue::Null, |number| SqlValue::Real(number as f64))
+        }
+        Property::People { people, .. } => list(people.iter().map(|person| &person.id).collect()),
+        Property::Relation { relation, .. } => list(relation.iter().map(|page| &page.id).collect()),
+        Property::RichText { rich_text, .. }
+        | Property::Title {
+            title: rich_text, ..
+        } => SqlValue::Text(plain_text(rich_text)),
+        _ => SqlValue::Null,
+    }
+}
+
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use std::sync::{Arc, Mutex};
+
+    fn page(id: &str, last_edited_time: &str, name: &str) -> Value {
+        json!({
+            "object": "page",
+            "id": id,
+            "created_by": { "id": "user" },
+            "url": format!("https://www.notion.so/{id}"),
+            "parent": { "type": "database_id", "database_id": "database" },
+            "created_time": "2024-01-01T00:00:00.000Z",
+            "last_edited_time": last_edited_time,
+            "cover": null,
+            "icon": null,
+            "archived": false,
+            "properties": {
+                "Name": { "id": "title", "type": "title", "title": [{
+                    "type": "text",
+                    "text": { "content": name, "link": null },
+                    "annotations": {
+                        "bold": false, "italic": false, "strikethrough": false,
+                        "underline": false, "code": false, "color": "default"
+                    },
+                    "plain_text": name,
+                    "href": null
+                }] },
+                "Points": { "id": "a%3Bc", "type": "number", "number": 3 }
+            }
+        })
+    }
+
+    fn client(pages: Arc<Mutex<Vec<Value>>>, filters: Arc<Mutex<Vec<Value>>>) -> Client {
+        Client::new()
+            .api_key("secret")
+            .custom_request(move |request| {
+                let pages = pages.clone();
+                let filters = filters.clone();
+
+                Box::pin(async move {
+                    let request = request.try_clone().unwrap().build()?;
+                    let body = if request.url().path().ends_with("/query") {
+                        let body = serde_json::from_slice::<Value>(
+                            request.body().and_then(|body| body.as_bytes()).unwrap(),
+                        )
+                        .unwrap();
+                        filters.lock().unwrap().push(body["filter"].clone());
+
+                        json!({
+                            "object": "list",
+                            "results": *pages.lock().unwrap(),
+                            "next_cursor": null,
+                            "has_more": false
+                        })
+                    } else {
+                        json!({
+                            "object": "database",
+                            "id": "database",
+                            "title": [],
+                            "description": [],
+                            "properties": {
+                                "Name": { "id": "title", "name": "Name", "type": "title", "title": {} },
+                                "Points": {
+                                    "id": "a%3Bc", "name": "Points", "type": "number",
+                                    "number": { "format": "number" }
+                                }
+                            },
+                            "url": "https://www.notion.so/database",
+                            "parent": { "type": "workspace", "workspace": true },
+                            "created_time": "2024-01-01T00:00:00.000Z",
+                            "last_edited_time": "2024-01-01T00:00:00.000Z",
+                            "last_edited_by": { "object": "user", "id": "user" },
+                            "icon": null,
+                            "cover": null,
+                            "archived": false,
+                            "is_inline": false
+                        })
+                    };
+
+                    Ok(reqwest::Response::from(http::Response::new(
+                        body.to_string(),
+                    )))
+                })
+            })
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn runs_continue_from_the_watermark() {
+        let pages = Arc::new(Mutex::new(vec![
+            page("a", "2024-01-02T00:00:00.000Z", "First"),
+            page("b", "2024-01-03T00:00:00.000Z", "Second"),
+        ]));
+        let filters = Arc::new(Mutex::new(Vec::new()));
+        let mut sync = DatabaseSync::new(
+            client(pages.clone(), filters.clone()),
+            Connection::open_in_memory().unwrap(),
+        )
+        .unwrap();
+        let options = |full| SyncOptions {
+            database_id: DatabaseId::new_unchecked("database"),
+            table: "tasks",
+            full,
+        };
+
+        let report = sync.sync(options(false)).await.unwrap();
+        assert_eq!(report.upserted, 2);
+        assert_eq!(
+            report.watermark.as_deref(),
+            Some("2024-01-03T00:00:00+00:00")
+        );
+
+        *pages.lock().unwrap() = vec![page("b", "2024-01-04T00:00:00.000Z", "Renamed")];
+        sync.sync(options(false)).await.unwrap();
+        assert_eq!(
+            filters.lock().unwrap()[1]["last_edited_time"]["on_or_after"],
+            "2024-01-03T00:00:00+00:00"
+        );
+
+        let report = sync.sync(options(true)).await.unwrap();
+        assert!(filters.lock().unwrap()[2].is_null());
+        assert_eq!(report.archived, 1);
+
+        let rows = sync
+            .connection()
+            .prepare("SELECT _id, Name, Points, _archived FROM tasks ORDER BY _id")
+            .unwrap()
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, f64>(2)?,
+                    row.get::<_, bool>(3)?,
+                ))
+            })
+            .unwrap()
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            [
+                ("a".to_owned(), "First".to_owned(), 3.0, true),
+                ("b".to_owned(), "Renamed".to_owned(), 3.0, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn properties_keep_columns_of_their_own() {
+        let schema = |properties: Value| {
+            serde_json::from_value::<HashMap<String, DatabaseProperty>>(properties).unwrap()
+        };
+        let text = |id: &str, value: &str| {
+            serde_json::from_value::<Property>(
+                json!({ "id": id, "type": "rich_text", "rich_text": [{
+                "type": "text",
+                "text": { "content": value, "link": null },
+                "annotations": {
+                    "bold": false, "italic": false, "strikethrough": false,
+                    "underline": false, "code": false, "color": "default"
+                },
+                "plain_text": value,
+                "href": null
+            }] }),
+            )
+            .unwrap()
+        };
+        let mut row =
+            serde_json::from_value::<Page>(page("a", "2024-01-02T00:00:00.000Z", "First")).unwrap();
+        let database_id = DatabaseId::new_unchecked("database");
+        let mut sync = DatabaseSync::new(
+            Client::new().api_key("secret").build().unwrap(),
+            Connection::open_in_memory().unwrap(),
+        )
+        .unwrap();
+
+        let first = schema(json!({
+            "_id": { "id": "u", "name": "_id", "type": "rich_text", "rich_text": {} },
+            "Name": { "id": "title", "name": "Name", "type": "title", "title": {} },
+            "a": { "id": "x", "name": "a", "type": "rich_text", "rich_text": {} }
+        }));
+        let columns = sync.create_table(&database_id, "tasks", &first).unwrap();
+        row.properties
+            .insert("_id".to_owned(), text("u", "custom id"));
+        row.properties.insert("a".to_owned(), text("x", "lower"));
+        upsert(sync.connection(), "tasks", &columns, &row).unwrap();
+
+        // A property that sorts before `a` and only differs in case shows up in the next run
+        let mut second = first.clone();
+        second.extend(schema(json!({
+            "A": { "id": "y", "name": "A", "type": "rich_text", "rich_text": {} }
+        })));
+        let columns = sync.create_table(&database_id, "tasks", &second).unwrap();
+        row.properties.insert("A".to_owned(), text("y", "upper"));
+        upsert(sync.connection(), "tasks", &columns, &row).unwrap();
+
+        assert_eq!(columns["u"], "_id_2");
+        assert_eq!(columns["x"], "a");
+        assert_eq!(columns["y"], "A_2");
+        let values = sync
+            .connection()
+            .query_row("SELECT _id, _id_2, Name, a, A_2 FROM tasks", [], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                ))
+            })
+            .unwrap();
+        assert_eq!(
+            values,
+            (
+                "a".to_owned(),
+                "custom id".to_owned(),
+                "First".to_owned(),
+                "lower".to_owned(),
+                "upper".to_owned()
+            )
+        );
+    }
+}
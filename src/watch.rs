@@ -0,0 +1,406 @@
+//! Polls a database (or the pages shared with the integration) for changes.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures_core::stream::BoxStream;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    Client, DatabaseId, DatabaseQueryOptions, Page, Property, QueryResponse, Result, SearchOptions,
+};
+
+/// What `Watcher` polls.
+#[derive(Clone)]
+pub enum WatchSource {
+    Database(DatabaseId),
+    /// All pages shared with the integration, found with the search endpoint.
+    Search,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChangeEvent {
+    Created {
+        page: Page,
+    },
+    Updated {
+        page: Page,
+        /// The names of the properties that differ from the last seen version of the page, empty
+        /// if only its content changed.
+        changed_properties: Vec<String>,
+    },
+    Archived {
+        page_id: String,
+    },
+}
+
+/// What a `Watcher` has seen so far, to persist between runs with `Watcher::state` and
+/// `Watcher::with_state`.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WatchState {
+    /// The `last_edited_time` of the most recently edited page seen.
+    pub watermark: Option<String>,
+    pub snapshots: HashMap<String, PageSnapshot>,
+    /// Polls since the last full scan.
+    pub polls: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PageSnapshot {
+    pub last_edited_time: String,
+    pub properties: HashMap<String, Property>,
+}
+
+/// Emits a `ChangeEvent` for every page created, edited or archived since the last poll.
+///
+/// Polls only fetch the pages edited since the watermark. Notion leaves archived pages out of
+/// queries and search results, so those are noticed by the full scan of every
+/// `full_scan_every`-th poll, which fetches all pages.
+#[derive(Clone)]
+pub struct Watcher {
+    client: Client,
+    source: WatchSource,
+    interval: Duration,
+    full_scan_every: u32,
+    emit_existing: bool,
+    state: Arc<Mutex<WatchState>>,
+}
+
+impl Watcher {
+    pub fn new(client: Client, source: WatchSource) -> Watcher {
+        Watcher {
+            client,
+            source,
+            interval: Duration::from_secs(60),
+            full_scan_every: 10,
+            emit_existing: false,
+            state: Arc::default(),
+        }
+    }
+
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+
+        self
+    }
+
+    pub fn full_scan_every(mut self, polls: u32) -> Self {
+        self.full_scan_every = polls.max(1);
+
+        self
+    }
+
+    /// Whether the first poll emits `Created` for every existing page, instead of silently
+    /// recording them.
+    pub fn emit_existing(mut self, emit_existing: bool) -> Self {
+        self.emit_existing = emit_existing;
+
+        self
+    }
+
+    /// Continues from a state saved by an earlier run.
+    pub fn with_state(self, state: WatchState) -> Self {
+        *self
+            .state
+            .lock()
+            .expect("watch state lock to not be poisoned") = state;
+
+        self
+    }
+
+    /// The current state, which can be saved while `changes` is running.
+    pub fn state(&self) -> WatchState {
+        self.state
+            .lock()
+            .expect("watch state lock to not be poisoned")
+            .clone()
+    }
+
+    /// Polls every `interval`, starting right away. Failed polls are yielded as errors and
+    /// retried on the next interval.
+    pub fn changes(&self) -> BoxStream<'static, Result<ChangeEvent>> {
+        let watcher = self.clone();
+
+        Box::pin(futures_util::stream::unfold(
+            (watcher, VecDeque::new(), true),
+            |(watcher, mut pending, mut first)| async move {
+                loop {
+                    if let Some(event) = pending.pop_front() {
+                        return Some((Ok(event), (watcher, pending, first)));
+                    }
+                    if !first {
+                        tokio::time::sleep(watcher.interval).await;
+                    }
+                    first = false;
+
+                    match watcher.poll().await {
+                        Ok(events) => pending.extend(events),
+                        Err(error) => return Some((Err(error), (watcher, pending, first))),
+                    }
+                }
+            },
+        ))
+    }
+
+    /// Fetches the pages edited since the last poll and returns what changed.
+    pub async fn poll(&self) -> Result<Vec<ChangeEvent>> {
+        let (watermark, full_scan, first) = {
+            let state = self
+                .state
+                .lock()
+                .expect("watch state lock to not be poisoned");
+            let first = state.watermark.is_none() && state.snapshots.is_empty();
+            let full_scan = first || state.polls + 1 >= self.full_scan_every;
+
+            (state.watermark.clone(), full_scan, first)
+        };
+
+        let mut pages = self
+            .fetch(if full_scan {
+                None
+            } else {
+                watermark.as_deref()
+            })
+            .await?;
+        pages.reverse();
+
+        let mut state = self
+            .state
+            .lock()
+            .expect("watch state lock to not be poisoned");
+        state.polls = if full_scan { 0 } else { state.polls + 1 };
+
+        let mut events = Vec::new();
+        let mut seen = HashSet::new();
+        for page in pages {
+            let last_edited_time = page.last_edited_time.to_string();
+            if state.watermark.as_ref() < Some(&last_edited_time) {
+                state.watermark = Some(last_edited_time.clone());
+            }
+
+            if page.archived || page.in_trash == Some(true) {
+                if state.snapshots.remove(&page.id).is_some() {
+                    events.push(ChangeEvent::Archived { page_id: page.id });
+                }
+                continue;
+            }
+
+            seen.insert(page.id.clone());
+            let snapshot = PageSnapshot {
+                last_edited_time: last_edited_time.clone(),
+                properties: page.properties.clone(),
+            };
+            match state.snapshots.insert(page.id.clone(), snapshot) {
+                None if first && !self.emit_existing => {}
+                None => events.push(ChangeEvent::Created { page }),
+                Some(previous) if previous.last_edited_time != last_edited_time => {
+                    let mut changed_properties = page
+                        .properties
+                        .iter()
+                        .filter(|(name, property)| previous.properties.get(*name) != Some(property))
+                        .map(|(name, _)| name.clone())
+                        .chain(
+                            previous
+                                .properties
+                                .keys()
+                                .filter(|name| !page.properties.contains_key(*name))
+                                .cloned(),
+                        )
+                        .collect::<Vec<_>>();
+                    changed_properties.sort();
+
+                    events.push(ChangeEvent::Updated {
+                        page,
+                        changed_properties,
+                    });
+                }
+                Some(_) => {}
+            }
+        }
+
+        if full_scan {
+            let mut gone = state
+                .snapshots
+                .keys()
+                .filter(|id| !seen.contains(*id))
+                .cloned()
+                .collect::<Vec<_>>();
+            gone.sort();
+            for page_id in gone {
+                state.snapshots.remove(&page_id);
+                events.push(ChangeEvent::Archived { page_id });
+            }
+        }
+
+        Ok(events)
+    }
+
+    /// Fetches the pages edited on or after `watermark`, or all of them.
+    async fn fetch(&self, watermark: Option<&str>) -> Result<Vec<Page>> {
+        let mut pages = Vec::new();
+        let mut start_cursor = None;
+
+        loop {
+            let results: QueryResponse<Page> = match &self.source {
+                WatchSource::Database(database_id) => {
+                    self.client
+                        .databases
+                        .query(DatabaseQueryOptions {
+                            database_id: database_id.clone(),
+                            // Notion rounds `last_edited_time` to minutes, so the minute of the
+                            // watermark is fetched again
+                            filter: watermark.map(|watermark| {
+                                json!({
+                                    "timestamp": "last_edited_time",
+                                    "last_edited_time": { "on_or_after": watermark }
+                                })
+                            }),
+                            sorts: Some(json!([
+                                { "timestamp": "last_edited_time", "direction": "descending" }
+                            ])),
+                            start_cursor: start_cursor.clone(),
+                        })
+                        .await?
+                }
+                WatchSource::Search => {
+                    self.client
+                        .clone()
+                        .search(SearchOptions {
+                            query: None,
+                            filter: Some(json!({ "property": "object", "value": "page" })),
+                            sort: Some(json!({
+                                "timestamp": "last_edited_time",
+                                "direction": "descending"
+                            })),
+                            start_cursor: start_cursor.as_deref(),
+                            page_size: Some(100),
+                        })
+                        .await?
+                }
+            };
+
+            // Search can't filter by time, so stop at the first page older than the watermark
+            let count = results.results.len();
+            let before = pages.len();
+            pages.extend(results.results.into_iter().take_while(|page| {
+                watermark
+                    .is_none_or(|watermark| page.last_edited_time.to_string().as_str() >= watermark)
+            }));
+            let older = pages.len() - before < count;
+
+            match results.next_cursor {
+                Some(next_cursor) if results.has_more && !older => start_cursor = Some(next_cursor),
+                _ => return Ok(pages),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+    use serde_json::Value;
+
+    fn page(id: &str, last_edited_time: &str, points: u32) -> Value {
+        json!({
+            "object": "page",
+            "id": id,
+            "created_by": { "id": "user" },
+            "url": format!("https://www.notion.so/{id}"),
+            "parent": { "type": "database_id", "database_id": "database" },
+            "created_time": "2024-01-01T00:00:00.000Z",
+            "last_edited_time": last_edited_time,
+            "cover": null,
+            "icon": null,
+            "archived": false,
+            "properties": {
+                "Points": { "id": "a%3Bc", "type": "number", "number": points },
+                "Done": { "id": "b%3Bd", "type": "checkbox", "checkbox": false }
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn polls_report_what_changed() {
+        let pages = Arc::new(Mutex::new(vec![
+            page("b", "2024-01-03T00:00:00.000Z", 2),
+            page("a", "2024-01-02T00:00:00.000Z", 1),
+        ]));
+        let filters = Arc::new(Mutex::new(Vec::new()));
+
+        let client = {
+            let pages = pages.clone();
+            let filters = filters.clone();
+
+            Client::new()
+                .api_key("secret")
+                .custom_request(move |request| {
+                    let pages = pages.clone();
+                    let filters = filters.clone();
+
+                    Box::pin(async move {
+                        let request = request.try_clone().unwrap().build()?;
+                        let body = serde_json::from_slice::<Value>(
+                            request.body().and_then(|body| body.as_bytes()).unwrap(),
+                        )
+                        .unwrap();
+                        filters.lock().unwrap().push(body["filter"].clone());
+                        let body = json!({
+                            "object": "list",
+                            "results": *pages.lock().unwrap(),
+                            "next_cursor": null,
+                            "has_more": false
+                        });
+
+                        Ok(reqwest::Response::from(http::Response::new(
+                            body.to_string(),
+                        )))
+                    })
+                })
+                .build()
+                .unwrap()
+        };
+        let watcher = Watcher::new(
+            client,
+            WatchSource::Database(DatabaseId::new_unchecked("database")),
+        )
+        .interval(Duration::ZERO)
+        .full_scan_every(2);
+
+        assert_eq!(watcher.poll().await.unwrap(), []);
+
+        *pages.lock().unwrap() = vec![
+            page("c", "2024-01-05T00:00:00.000Z", 3),
+            page("b", "2024-01-04T00:00:00.000Z", 5),
+        ];
+        let events = watcher.poll().await.unwrap();
+        assert_eq!(
+            filters.lock().unwrap()[1]["last_edited_time"]["on_or_after"],
+            "2024-01-03T00:00:00+00:00"
+        );
+        assert!(matches!(
+            &events[..],
+            [
+                ChangeEvent::Updated { page, changed_properties },
+                ChangeEvent::Created { .. }
+            ] if page.id == "b" && changed_properties == &["Points"]
+        ));
+
+        // Every second poll is a full scan, which notices that `a` is gone
+        let state = watcher.state();
+        let watcher = Watcher::new(watcher.client.clone(), watcher.source.clone())
+            .full_scan_every(2)
+            .with_state(state);
+        let events = watcher.changes().next().await.unwrap().unwrap();
+        assert!(filters.lock().unwrap()[2].is_null());
+        assert_eq!(
+            events,
+            ChangeEvent::Archived {
+                page_id: "a".to_owned()
+            }
+        );
+    }
+}
@@ -0,0 +1,406 @@
+//! Payloads of Notion webhooks and the verification of their signatures.
+//!
+//! When a subscription is created, Notion first sends an unsigned request with the verification
+//! token that signs all later events in the `X-Notion-Signature` header. Apps have to opt into
+//! accepting that request, as anyone can send one.
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+
+use crate::{DateValue, Error, Result};
+
+pub const SIGNATURE_HEADER: &str = "X-Notion-Signature";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WebhookRequest {
+    /// The one-time request that hands out the verification token of a new subscription.
+    Verification {
+        verification_token: String,
+    },
+    Event(Box<WebhookEvent>),
+}
+
+/// The body of the verification request, which has nothing but the token.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Verification {
+    verification_token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WebhookEvent {
+    pub id: String,
+    pub timestamp: DateValue,
+    pub workspace_id: String,
+    pub workspace_name: Option<String>,
+    pub subscription_id: String,
+    pub integration_id: String,
+    #[serde(default)]
+    pub authors: Vec<WebhookAuthor>,
+    pub attempt_number: u32,
+    /// The page, database or comment the event is about.
+    pub entity: WebhookEntity,
+    #[serde(flatten)]
+    pub data: WebhookEventData,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WebhookAuthor {
+    pub id: String,
+    /// `person`, `bot` or `agent`.
+    #[serde(rename = "type")]
+    pub author_type: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WebhookEntity {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub entity_type: WebhookEntityType,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEntityType {
+    Page,
+    Database,
+    DataSource,
+    Block,
+    Comment,
+    /// The workspace, as the parent of top-level pages.
+    Space,
+    #[serde(other)]
+    Unsupported,
+}
+
+/// The `type` of an event and its `data`. Events of other types, or whose `data` doesn't match their
+/// type, are `Unsupported`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type", content = "data")]
+pub enum WebhookEventData {
+    #[serde(rename = "page.created")]
+    PageCreated(ParentData),
+    #[serde(rename = "page.properties_updated")]
+    PagePropertiesUpdated(PropertiesUpdatedData),
+    #[serde(rename = "page.content_updated")]
+    PageContentUpdated(ContentUpdatedData),
+    #[serde(rename = "page.moved")]
+    PageMoved(ParentData),
+    #[serde(rename = "page.deleted")]
+    PageDeleted(ParentData),
+    #[serde(rename = "page.undeleted")]
+    PageUndeleted(ParentData),
+    #[serde(rename = "page.locked")]
+    PageLocked(ParentData),
+    #[serde(rename = "page.unlocked")]
+    PageUnlocked(ParentData),
+    #[serde(rename = "database.created")]
+    DatabaseCreated(ParentData),
+    #[serde(rename = "database.content_updated")]
+    DatabaseContentUpdated(ContentUpdatedData),
+    #[serde(rename = "database.moved")]
+    DatabaseMoved(ParentData),
+    #[serde(rename = "database.deleted")]
+    DatabaseDeleted(ParentData),
+    #[serde(rename = "database.undeleted")]
+    DatabaseUndeleted(ParentData),
+    #[serde(rename = "database.schema_updated")]
+    DatabaseSchemaUpdated(SchemaUpdatedData),
+    #[serde(rename = "comment.created")]
+    CommentCreated(CommentData),
+    #[serde(rename = "comment.updated")]
+    CommentUpdated(CommentData),
+    #[serde(rename = "comment.deleted")]
+    CommentDeleted(CommentData),
+    #[serde(untagged)]
+    Unsupported {
+        #[serde(rename = "type")]
+        event_type: String,
+        #[serde(default)]
+        data: Value,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ParentData {
+    pub parent: WebhookEntity,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PropertiesUpdatedData {
+    pub parent: WebhookEntity,
+    /// The ids of the updated properties.
+    pub updated_properties: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ContentUpdatedData {
+    pub parent: WebhookEntity,
+    #[serde(default)]
+    pub updated_blocks: Vec<WebhookEntity>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SchemaUpdatedData {
+    pub parent: WebhookEntity,
+    pub updated_properties: Vec<SchemaChange>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SchemaChange {
+    pub id: String,
+    pub name: String,
+    pub action: SchemaAction,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaAction {
+    Created,
+    Updated,
+    Deleted,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CommentData {
+    pub page_id: String,
+    pub parent: WebhookEntity,
+}
+
+/// Checks the `X-Notion-Signature` header (`sha256=<hex>`) of a request against the HMAC of its
+/// raw body.
+pub fn verify_signature(body: &[u8], signature: &str, verification_token: &str) -> bool {
+    let Some(signature) = signature
+        .strip_prefix("sha256=")
+        .and_then(|signature| hex::decode(signature).ok())
+    else {
+        return false;
+    };
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(verification_token.as_bytes())
+        .expect("HMAC to accept keys of any length");
+    mac.update(body);
+
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// Parses the unsigned request that hands out the verification token of a new subscription.
+/// Anything but a body with just the token is rejected.
+pub fn parse_verification(body: &[u8]) -> Result<String> {
+    let Verification { verification_token } = serde_json::from_slice(body)?;
+
+    Ok(verification_token)
+}
+
+/// Verifies the signature of an event before parsing it.
+pub fn parse_event(body: &[u8], signature: &str, verification_token: &str) -> Result<WebhookEvent> {
+    if !verify_signature(body, signature, verification_token) {
+        return Err(Error::InvalidSignature);
+    }
+
+    Ok(serde_json::from_slice(body)?)
+}
+
+/// Parses the body of a webhook request. Signed requests are events, and unsigned ones are only
+/// accepted as the verification request if `accept_verification` is set.
+pub fn parse_request(
+    body: &[u8],
+    signature: Option<&str>,
+    verification_token: &str,
+    accept_verification: bool,
+) -> Result<WebhookRequest> {
+    match signature {
+        Some(signature) => Ok(WebhookRequest::Event(Box::new(parse_event(
+            body,
+            signature,
+            verification_token,
+        )?))),
+        None if accept_verification => Ok(WebhookRequest::Verification {
+            verification_token: parse_verification(body)?,
+        }),
+        None => Err(Error::InvalidSignature),
+    }
+}
+
+#[cfg(feature = "webhook-axum")]
+pub use extract::{NotionWebhook, WebhookSecret};
+
+#[cfg(feature = "webhook-axum")]
+mod extract {
+    use axum::async_trait;
+    use axum::body::Bytes;
+    use axum::extract::{FromRef, FromRequest, Request};
+    use axum::http::StatusCode;
+
+    use super::{parse_request, WebhookRequest, SIGNATURE_HEADER};
+    use crate::Error;
+
+    /// The verification token of the subscription, taken from the router state.
+    #[derive(Debug, Clone, Default)]
+    pub struct WebhookSecret {
+        pub verification_token: String,
+        /// Whether to accept the unsigned verification request, while setting up a subscription.
+        pub accept_verification: bool,
+    }
+
+    impl WebhookSecret {
+        pub fn new(verification_token: &str) -> WebhookSecret {
+            WebhookSecret {
+                verification_token: verification_token.to_owned(),
+                accept_verification: false,
+            }
+        }
+    }
+
+    /// Extracts a verified `WebhookRequest`, rejecting unsigned requests (unless they are an
+    /// accepted verification request) and those with an invalid signature with `401 Unauthorized`,
+    /// and unparsable ones with `400 Bad Request`.
+    #[derive(Debug, Clone)]
+    pub struct NotionWebhook(pub WebhookRequest);
+
+    #[async_trait]
+    impl<S> FromRequest<S> for NotionWebhook
+    where
+        S: Send + Sync,
+        WebhookSecret: FromRef<S>,
+    {
+        type Rejection = (StatusCode, String);
+
+        async fn from_request(
+            request: Request,
+            state: &S,
+        ) -> std::result::Result<Self, Self::Rejection> {
+            let secret = WebhookSecret::from_ref(state);
+            let signature = request
+                .headers()
+                .get(SIGNATURE_HEADER)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_owned);
+            let body = Bytes::from_request(request, state)
+                .await
+                .map_err(|rejection| (StatusCode::BAD_REQUEST, rejection.body_text()))?;
+
+            match parse_request(
+                &body,
+                signature.as_deref(),
+                &secret.verification_token,
+                secret.accept_verification,
+            ) {
+                Ok(request) => Ok(NotionWebhook(request)),
+                Err(Error::InvalidSignature) => {
+                    Err((StatusCode::UNAUTHORIZED, "invalid signature".to_owned()))
+                }
+                Err(error) => Err((StatusCode::BAD_REQUEST, error.to_string())),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const TOKEN: &str = "secret_tMrlL1qK5vuQAh1b6cZGhFChZTSYJlce98V0pYn7yBl";
+
+    fn sign(body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(TOKEN.as_bytes()).unwrap();
+        mac.update(body);
+
+        format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+    }
+
+    #[test]
+    fn events_are_verified_and_typed() {
+        let body = json!({
+            "id": "367cba44-b6f3-4c92-81e7-6a2e9659efd4",
+            "timestamp": "2024-12-05T23:57:05.379Z",
+            "workspace_id": "13950b26-c203-4f3b-b97d-93ec06319565",
+            "workspace_name": "Quantify Labs",
+            "subscription_id": "29d75c0d-5546-4414-8459-7b7a92f1fc4b",
+            "integration_id": "0ef2e755-4912-8096-91c1-00376a88a5ca",
+            "type": "database.schema_updated",
+            "authors": [{ "id": "c7c11cca-1d73-471d-9b6e-bdef51470190", "type": "person" }],
+            "attempt_number": 1,
+            "entity": { "id": "153104cd-477e-80ec-b1b6-c3fc6a3cbf32", "type": "database" },
+            "data": {
+                "parent": { "id": "13950b26-c203-4f3b-b97d-93ec06319565", "type": "space" },
+                "updated_properties": [
+                    { "id": "fy:{", "name": "Description", "action": "created" }
+                ]
+            }
+        })
+        .to_string();
+
+        let request =
+            parse_request(body.as_bytes(), Some(&sign(body.as_bytes())), TOKEN, false).unwrap();
+        let WebhookRequest::Event(event) = request else {
+            panic!("expected an event");
+        };
+        assert_eq!(event.entity.entity_type, WebhookEntityType::Database);
+        assert!(matches!(
+            event.data,
+            WebhookEventData::DatabaseSchemaUpdated(SchemaUpdatedData { ref updated_properties, .. })
+                if updated_properties[0].action == SchemaAction::Created
+        ));
+
+        assert!(matches!(
+            parse_request(body.as_bytes(), Some("sha256=00"), TOKEN, false),
+            Err(Error::InvalidSignature)
+        ));
+        assert!(matches!(
+            parse_request(body.as_bytes(), None, TOKEN, true),
+            Err(Error::Deserialization(..))
+        ));
+    }
+
+    #[test]
+    fn events_of_other_types_are_unsupported() {
+        let body = json!({
+            "id": "367cba44-b6f3-4c92-81e7-6a2e9659efd4",
+            "timestamp": "2024-12-05T23:57:05.379Z",
+            "workspace_id": "13950b26-c203-4f3b-b97d-93ec06319565",
+            "subscription_id": "29d75c0d-5546-4414-8459-7b7a92f1fc4b",
+            "integration_id": "0ef2e755-4912-8096-91c1-00376a88a5ca",
+            "type": "data_source.schema_updated",
+            "attempt_number": 1,
+            "entity": { "id": "153104cd-477e-80ec-b1b6-c3fc6a3cbf32", "type": "data_source" },
+            "data": { "parent": { "id": "13950b26-c203-4f3b-b97d-93ec06319565", "type": "database" } }
+        })
+        .to_string();
+
+        let event = parse_event(body.as_bytes(), &sign(body.as_bytes()), TOKEN).unwrap();
+        assert!(matches!(
+            event.data,
+            WebhookEventData::Unsupported { ref event_type, ref data }
+                if event_type == "data_source.schema_updated" && data["parent"]["type"] == "database"
+        ));
+    }
+
+    #[test]
+    fn verification_requests_have_to_be_accepted() {
+        let body = json!({ "verification_token": TOKEN }).to_string();
+
+        assert_eq!(
+            parse_request(body.as_bytes(), None, "", true).unwrap(),
+            WebhookRequest::Verification {
+                verification_token: TOKEN.to_owned()
+            }
+        );
+        assert!(matches!(
+            parse_request(body.as_bytes(), None, "", false),
+            Err(Error::InvalidSignature)
+        ));
+
+        // An unsigned event can't pass for a verification request
+        let spoofed = json!({
+            "verification_token": "evil",
+            "type": "page.created",
+            "entity": { "id": "153104cd-477e-80ec-b1b6-c3fc6a3cbf32", "type": "page" }
+        })
+        .to_string();
+        assert!(parse_request(spoofed.as_bytes(), None, "", true).is_err());
+    }
+}